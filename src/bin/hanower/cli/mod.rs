@@ -9,11 +9,20 @@ mod subcommand;
 
 // use core::Interval;
 
-use crate::{config::Options, run::Runner};
+use crate::{config::Options, notation::NumberFormat, output::Format, run::Runner};
 use structopt::StructOpt;
 
 #[derive(Debug, StructOpt)]
+#[structopt(about)]
 pub struct Root {
+    /// Output format: `words`, `lines`, `nul`, `csv`, `json`, `jsonl` or `table`.
+    /// Structured formats include metadata such as fence indexes and bucket bounds
+    #[structopt(long, global = true)]
+    format: Option<Format>,
+
+    #[structopt(flatten)]
+    numbers: NumberFormat,
+
     #[structopt(subcommand)]
     cmd: Command,
 }
@@ -24,17 +33,46 @@ impl Root {
     }
 
     pub fn execute(&mut self) -> Result<(), color_eyre::eyre::Report> {
-        let cfg = Options::V1;
         let mut output = std::io::stdout();
 
-        self.cmd.run(&mut output, Some(cfg))
+        self.execute_to(&mut output)
     }
+
+    fn execute_to(&mut self, dst: &mut dyn std::io::Write) -> Result<(), color_eyre::eyre::Report> {
+        let cfg = Options::V1 {
+            format: self.format,
+            numbers: self.numbers,
+        };
+
+        self.cmd.run(dst, Some(cfg))
+    }
+}
+
+/// Runs hanower with `args` as if given on the command line, returning what it printed
+#[cfg(test)]
+fn run_cli(args: &[&str]) -> Result<String, color_eyre::eyre::Report> {
+    let mut root = Root::from_iter_safe(std::iter::once("hanower").chain(args.iter().copied()))?;
+    let mut output = Vec::new();
+
+    root.execute_to(&mut output)?;
+
+    Ok(String::from_utf8(output)?)
 }
 
 #[derive(Debug, StructOpt)]
 enum Command {
     Range(subcommand::Range),
     Interval(subcommand::SubComInterval),
+    Snap(subcommand::Snap),
+    Codegen(subcommand::Codegen),
+    Rotate(subcommand::Rotate),
+    Retain(subcommand::Retain),
+    Downsample(subcommand::Downsample),
+    Pick(subcommand::Pick),
+    GitSample(subcommand::GitSample),
+    Sweep(subcommand::Sweep),
+    Probe(subcommand::Probe),
+    Grid(subcommand::SubComGrid),
 }
 
 impl Runner for Command {
@@ -48,6 +86,31 @@ impl Runner for Command {
         match self {
             Self::Range(cmd) => cmd.run(dst, config),
             Self::Interval(cmd) => cmd.run(dst, config),
+            Self::Snap(cmd) => cmd.run(dst, config),
+            Self::Codegen(cmd) => cmd.run(dst, config),
+            Self::Rotate(cmd) => cmd.run(dst, config),
+            Self::Retain(cmd) => cmd.run(dst, config),
+            Self::Downsample(cmd) => cmd.run(dst, config),
+            Self::Pick(cmd) => cmd.run(dst, config),
+            Self::GitSample(cmd) => cmd.run(dst, config),
+            Self::Sweep(cmd) => cmd.run(dst, config),
+            Self::Probe(cmd) => cmd.run(dst, config),
+            Self::Grid(cmd) => cmd.run(dst, config),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn help_describes_the_crate() {
+        let mut help = Vec::new();
+        Root::clap().write_long_help(&mut help).unwrap();
+        let help = String::from_utf8(help).unwrap();
+
+        assert!(help.contains(env!("CARGO_PKG_DESCRIPTION")), "{}", help);
+        assert!(!help.contains("How every subcommand prints numbers"));
+    }
+}
@@ -0,0 +1,68 @@
+/*
+ * This Source Code Form is subject to the terms of
+ * the Mozilla Public License, v. 2.0. If a copy of
+ * the MPL was not distributed with this file, You
+ * can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use super::args::IntervalArg;
+use crate::output::{Row, Value};
+use hanower::{Bucket, Interval, UnitFamily};
+
+/// Buckets ages in seconds, whatever unit their interval spec was written in,
+/// so that `1h..=365d/12` and `1h..=8760h/12` have the same buckets
+#[derive(Debug, Clone, Copy)]
+pub(super) struct AgeBuckets {
+    /// Interval of ages, in seconds
+    pub interval: Interval,
+}
+
+impl AgeBuckets {
+    pub fn new(spec: IntervalArg) -> Result<Self, color_eyre::eyre::Report> {
+        match spec.unit {
+            Some(unit) if unit.family() != UnitFamily::Duration => {
+                color_eyre::eyre::bail!("`{}` is not a duration", unit)
+            }
+            _ => Ok(Self {
+                interval: spec.interval,
+            }),
+        }
+    }
+
+    /// Adds the bucket `age` falls in to `row`
+    pub fn with_bucket(&self, row: Row, age: f64) -> Row {
+        let bucket = self
+            .interval
+            .bucket(age)
+            .and_then(|index| self.interval.bucket_bounds(index));
+
+        with_bounds(row, bucket)
+    }
+}
+
+/// Adds the index and bounds of `bucket` to `row`, as durations, or empty fields if there is none
+pub(super) fn with_bounds(row: Row, bucket: Option<Bucket>) -> Row {
+    let seconds = |seconds: f64| Value::Quantity(seconds, UnitFamily::Duration.best_for(seconds));
+
+    row.with("bucket", bucket.map(|b| b.index() as u64))
+        .with("low", bucket.map_or(Value::Missing, |b| seconds(b.low())))
+        .with("high", bucket.map_or(Value::Missing, |b| seconds(b.high())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets_in_seconds() {
+        let days = AgeBuckets::new("1h..=365d/12".parse().unwrap()).unwrap();
+        let hours = AgeBuckets::new("1h..=8760h/12".parse().unwrap()).unwrap();
+
+        assert_eq!(
+            days.interval.intervals().collect::<Vec<_>>(),
+            hours.interval.intervals().collect::<Vec<_>>()
+        );
+        assert_eq!(Some(3600.0), days.interval.fence(0));
+        assert!(AgeBuckets::new("1KiB..=1MiB/4".parse().unwrap()).is_err());
+    }
+}
@@ -0,0 +1,36 @@
+/*
+ * This Source Code Form is subject to the terms of
+ * the Mozilla Public License, v. 2.0. If a copy of
+ * the MPL was not distributed with this file, You
+ * can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use hanower::{Interval, ParseIntervalError, Unit, UnitFamily};
+use std::str::FromStr;
+
+/// An Interval spec given on the command line, such as `100ms..=30s/8`,
+/// which remembers the unit its bounds were written in.
+#[derive(Debug, Clone, Copy)]
+pub struct IntervalArg {
+    pub interval: Interval,
+    pub unit: Option<Unit>,
+}
+
+impl FromStr for IntervalArg {
+    type Err = ParseIntervalError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Interval::parse_with_unit(s).map(|(interval, unit)| Self { interval, unit })
+    }
+}
+
+/// Whether only integers are of use across `interval`: its bounds are whole and
+/// either plain numbers or byte sizes, which have no use for fractions
+pub fn integral(interval: &Interval, unit: Option<Unit>) -> bool {
+    let whole = interval.low().fract() == 0.0 && interval.high().fract() == 0.0;
+
+    match unit.map(|unit| unit.family()) {
+        None | Some(UnitFamily::DecimalBytes) | Some(UnitFamily::BinaryBytes) => whole,
+        Some(UnitFamily::Duration) | Some(UnitFamily::Si) => false,
+    }
+}
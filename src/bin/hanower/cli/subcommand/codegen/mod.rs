@@ -0,0 +1,435 @@
+/*
+ * This Source Code Form is subject to the terms of
+ * the Mozilla Public License, v. 2.0. If a copy of
+ * the MPL was not distributed with this file, You
+ * can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use super::*;
+use args::IntervalArg;
+use hanower::Interval;
+use std::str::FromStr;
+
+/// Prints the fences of an interval as source code, along with a lookup function
+/// which finds the bucket of a number like `range` does. Ignores `--format`
+#[derive(Debug, StructOpt)]
+pub struct Codegen {
+    /// Interval spec whose fences are printed, such as `1..=1024/10`
+    #[structopt(long, allow_hyphen_values = true)]
+    spec: IntervalArg,
+
+    /// Language to print: `rust`, `c`, `python`, `go`, `ts` or `json-schema`
+    #[structopt(long)]
+    lang: Lang,
+
+    /// Base name of the generated items, such as `latency_ms`, cased to suit the language.
+    /// Words may be separated by `_`, `-`, `.` or spaces
+    #[structopt(long, default_value = "buckets")]
+    name: Name,
+}
+
+impl Runner for Codegen {
+    type Config = Options;
+
+    fn run(
+        &mut self,
+        dst: &mut dyn std::io::Write,
+        _config: Option<Self::Config>,
+    ) -> Result<(), color_eyre::eyre::Report> {
+        write!(
+            dst,
+            "{}",
+            generate(&self.spec.interval, self.lang, &self.name)
+        )?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Lang {
+    Rust,
+    C,
+    Python,
+    Go,
+    TypeScript,
+    JsonSchema,
+}
+
+impl FromStr for Lang {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "rust" | "rs" => Ok(Self::Rust),
+            "c" => Ok(Self::C),
+            "python" | "py" => Ok(Self::Python),
+            "go" => Ok(Self::Go),
+            "ts" | "typescript" => Ok(Self::TypeScript),
+            "json-schema" => Ok(Self::JsonSchema),
+            other => Err(format!(
+                "Unknown language. Ensure it is one of `rust`, `c`, `python`, `go`, `ts` or `json-schema` (was: {})",
+                other
+            )),
+        }
+    }
+}
+
+/// Writes the fences of `interval` and a bucket lookup in `lang`.
+///
+/// The lookup searches the fences rather than repeating the interval's formula,
+/// so it needs nothing beyond the array. Like `Interval::bucket`, it covers
+/// `low <= number < high` and nothing else
+fn generate(interval: &Interval, lang: Lang, name: &Name) -> String {
+    // Debug always writes a decimal point or exponent, which every target reads as a float
+    let fences: Vec<String> = interval.intervals().map(|f| format!("{:?}", f)).collect();
+    let len = fences.len();
+    let constant = name.constant();
+    let function = name.snake();
+
+    match lang {
+        Lang::Rust => format!(
+            r#"/// Fences of `{spec}`
+pub const {constant}: [f64; {len}] = [{fences}];
+
+/// Returns the index of the bucket `number` falls in, if it is in `{constant}[0]..{constant}[{last}]`
+pub fn {function}_bucket(number: f64) -> Option<usize> {{
+    if !(number >= {constant}[0] && number < {constant}[{last}]) {{
+        return None;
+    }}
+
+    Some({constant}.partition_point(|&fence| fence <= number) - 1)
+}}
+"#,
+            spec = interval,
+            constant = constant,
+            function = function,
+            len = len,
+            last = len - 1,
+            fences = fences.join(", "),
+        ),
+        Lang::C => format!(
+            r#"/* Fences of `{spec}` */
+#define {constant}_LEN {len}
+static const double {constant}[{constant}_LEN] = {{{fences}}};
+
+/* Returns the index of the bucket `number` falls in, or -1 if it is outside the fences */
+static inline int {function}_bucket(double number)
+{{
+    if (!(number >= {constant}[0] && number < {constant}[{constant}_LEN - 1])) {{
+        return -1;
+    }}
+
+    int low = 0, high = {constant}_LEN - 1;
+    while (high - low > 1) {{
+        int mid = low + (high - low) / 2;
+        if ({constant}[mid] <= number) {{
+            low = mid;
+        }} else {{
+            high = mid;
+        }}
+    }}
+
+    return low;
+}}
+"#,
+            spec = interval,
+            constant = constant,
+            function = function,
+            len = len,
+            fences = fences.join(", "),
+        ),
+        Lang::Python => format!(
+            r#"from bisect import bisect_right
+from typing import Optional
+
+# Fences of `{spec}`
+{constant} = ({fences},)
+
+
+def {function}_bucket(number: float) -> Optional[int]:
+    """Returns the index of the bucket `number` falls in, if it is inside the fences"""
+    if not ({constant}[0] <= number < {constant}[-1]):
+        return None
+
+    return bisect_right({constant}, number) - 1
+"#,
+            spec = interval,
+            constant = constant,
+            function = function,
+            fences = fences.join(", "),
+        ),
+        Lang::Go => format!(
+            r#"// {pascal} holds the fences of `{spec}`
+var {pascal} = [{len}]float64{{{fences}}}
+
+// {pascal}Bucket returns the index of the bucket number falls in, and false if it is outside the fences
+func {pascal}Bucket(number float64) (int, bool) {{
+	if !(number >= {pascal}[0] && number < {pascal}[{last}]) {{
+		return 0, false
+	}}
+
+	low, high := 0, {last}
+	for high-low > 1 {{
+		mid := low + (high-low)/2
+		if {pascal}[mid] <= number {{
+			low = mid
+		}} else {{
+			high = mid
+		}}
+	}}
+
+	return low, true
+}}
+"#,
+            spec = interval,
+            pascal = name.pascal(),
+            len = len,
+            last = len - 1,
+            fences = fences.join(", "),
+        ),
+        Lang::TypeScript => format!(
+            r#"/** Fences of `{spec}` */
+export const {constant}: readonly number[] = [{fences}];
+
+/** Returns the index of the bucket `number` falls in, if it is inside the fences */
+export function {camel}Bucket(number: number): number | undefined {{
+  if (!(number >= {constant}[0] && number < {constant}[{last}])) {{
+    return undefined;
+  }}
+
+  let low = 0;
+  let high = {last};
+  while (high - low > 1) {{
+    const mid = low + Math.floor((high - low) / 2);
+    if ({constant}[mid] <= number) {{
+      low = mid;
+    }} else {{
+      high = mid;
+    }}
+  }}
+
+  return low;
+}}
+"#,
+            spec = interval,
+            constant = constant,
+            camel = name.camel(),
+            last = len - 1,
+            fences = fences.join(", "),
+        ),
+        Lang::JsonSchema => {
+            // One subschema per bucket, so validating a number also finds its bucket
+            let buckets: Vec<String> = fences
+                .windows(2)
+                .enumerate()
+                .map(|(index, pair)| {
+                    format!(
+                        r#"    {{ "title": "{index}", "minimum": {low}, "exclusiveMaximum": {high} }}"#,
+                        index = index,
+                        low = pair[0],
+                        high = pair[1],
+                    )
+                })
+                .collect();
+
+            format!(
+                r#"{{
+  "$schema": "https://json-schema.org/draft/2020-12/schema",
+  "title": "{pascal}",
+  "description": "A number within the fences of `{spec}`, each bucket is titled with its index",
+  "type": "number",
+  "oneOf": [
+{buckets}
+  ]
+}}
+"#,
+                spec = interval,
+                pascal = name.pascal(),
+                buckets = buckets.join(",\n"),
+            )
+        }
+    }
+}
+
+/// A name which every language can build identifiers from, split into its words
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Name {
+    words: Vec<String>,
+}
+
+impl Name {
+    /// `LATENCY_MS`
+    fn constant(&self) -> String {
+        self.joined(str::to_uppercase, "_")
+    }
+
+    /// `latency_ms`
+    fn snake(&self) -> String {
+        self.joined(str::to_lowercase, "_")
+    }
+
+    /// `LatencyMs`
+    fn pascal(&self) -> String {
+        self.joined(capitalize, "")
+    }
+
+    /// `latencyMs`
+    fn camel(&self) -> String {
+        let (first, rest) = self
+            .words
+            .split_first()
+            .expect("a name has at least one word");
+
+        std::iter::once(first.to_lowercase())
+            .chain(rest.iter().map(|word| capitalize(word)))
+            .collect()
+    }
+
+    fn joined(&self, case: impl Fn(&str) -> String, separator: &str) -> String {
+        self.words
+            .iter()
+            .map(|word| case(word))
+            .collect::<Vec<_>>()
+            .join(separator)
+    }
+}
+
+impl FromStr for Name {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words: Vec<String> = s
+            .split(|c: char| c == '_' || c == '-' || c == '.' || c.is_whitespace())
+            .filter(|word| !word.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        match words.first() {
+            Some(first)
+                if !first.starts_with(|c: char| c.is_ascii_digit())
+                    && words
+                        .iter()
+                        .all(|word| word.chars().all(|c| c.is_ascii_alphanumeric())) =>
+            {
+                Ok(Self { words })
+            }
+            _ => Err(format!(
+                "Invalid name. Ensure it is made of ASCII letters and digits, separated by `_`, `-`, \
+                 `.` or spaces, and does not start with a digit (was: {})",
+                s
+            )),
+        }
+    }
+}
+
+/// Upper cases the first letter of `word`
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate(lang: Lang, name: &str) -> String {
+        let interval = Interval::new(1.0, 100.0, 2).unwrap();
+
+        super::generate(&interval, lang, &name.parse().unwrap())
+    }
+
+    #[test]
+    fn generate_rust() {
+        let code = generate(Lang::Rust, "latency");
+
+        assert!(code.contains("pub const LATENCY: [f64; 3] = [1.0, 10.0, 100.0];"));
+        assert!(code.contains("pub fn latency_bucket(number: f64) -> Option<usize>"));
+    }
+
+    #[test]
+    fn generate_c() {
+        let code = generate(Lang::C, "latency ms");
+
+        assert!(code.contains("#define LATENCY_MS_LEN 3\n"));
+        assert!(
+            code.contains("static const double LATENCY_MS[LATENCY_MS_LEN] = {1.0, 10.0, 100.0};")
+        );
+        assert!(code.contains("static inline int latency_ms_bucket(double number)"));
+        assert!(code.contains("return -1;"));
+    }
+
+    #[test]
+    fn generate_python() {
+        let code = generate(Lang::Python, "latency.ms");
+
+        assert!(code.contains("LATENCY_MS = (1.0, 10.0, 100.0,)\n"));
+        assert!(code.contains("def latency_ms_bucket(number: float) -> Optional[int]:"));
+        assert!(code.contains("return bisect_right(LATENCY_MS, number) - 1"));
+    }
+
+    #[test]
+    fn generate_go() {
+        let code = generate(Lang::Go, "latency_ms");
+
+        assert!(code.contains("var LatencyMs = [3]float64{1.0, 10.0, 100.0}\n"));
+        assert!(code.contains("func LatencyMsBucket(number float64) (int, bool) {"));
+        assert!(code.contains("\tlow, high := 0, 2\n"));
+    }
+
+    #[test]
+    fn generate_ts() {
+        let code = generate(Lang::TypeScript, "latency-ms");
+
+        assert!(code.contains("export const LATENCY_MS: readonly number[] = [1.0, 10.0, 100.0];"));
+        assert!(
+            code.contains("export function latencyMsBucket(number: number): number | undefined {")
+        );
+        assert!(code.contains("number < LATENCY_MS[2]"));
+    }
+
+    #[test]
+    fn generate_json_schema() {
+        let code = generate(Lang::JsonSchema, "latency_ms");
+        let schema: serde_json::Value = serde_json::from_str(&code).unwrap();
+
+        assert_eq!("LatencyMs", schema["title"]);
+        assert_eq!(
+            serde_json::json!([
+                { "title": "0", "minimum": 1.0, "exclusiveMaximum": 10.0 },
+                { "title": "1", "minimum": 10.0, "exclusiveMaximum": 100.0 },
+            ]),
+            schema["oneOf"]
+        );
+    }
+
+    #[test]
+    fn name_cases() {
+        let name: Name = "latency_ms".parse().unwrap();
+        assert_eq!(
+            ("LATENCY_MS", "latency_ms", "LatencyMs", "latencyMs"),
+            (
+                name.constant().as_str(),
+                name.snake().as_str(),
+                name.pascal().as_str(),
+                name.camel().as_str()
+            )
+        );
+
+        let name: Name = " p99.HTTP-latency ".parse().unwrap();
+        assert_eq!("P99HTTPLatency", name.pascal());
+        assert_eq!("p99HTTPLatency", name.camel());
+        assert_eq!("P99_HTTP_LATENCY", name.constant());
+    }
+
+    #[test]
+    fn name_err() {
+        for bad in &["", "  ", "9lives", "latency/ms", "ß", "a+b", "_1x"] {
+            assert!(bad.parse::<Name>().is_err(), "{:?}", bad);
+        }
+    }
+}
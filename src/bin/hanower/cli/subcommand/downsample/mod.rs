@@ -0,0 +1,463 @@
+/*
+ * This Source Code Form is subject to the terms of
+ * the Mozilla Public License, v. 2.0. If a copy of
+ * the MPL was not distributed with this file, You
+ * can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use super::*;
+use crate::{
+    output::{Format, Output, Row, Value},
+    timestamp::Timestamp,
+};
+use ages::{with_bounds, AgeBuckets};
+use args::IntervalArg;
+use hanower::{ByBucket, Count, Keyed, Last, Max, Min, Reducer, Sum};
+use std::{
+    fs::File,
+    io::{self, BufReader, Read},
+    path::PathBuf,
+};
+
+/// Shrinks a time series by aggregating its rows into buckets of age, so recent
+/// data stays dense and old data becomes coarse.
+///
+/// Reads `timestamp,value` rows, where timestamps are seconds since the epoch or
+/// RFC 3339 dates. Each bucket is printed as its last value, timed at it, so the
+/// output reads as a time series again. Rows younger than the interval are printed
+/// as they are, while those older are aggregated into a single row
+#[derive(Debug, StructOpt)]
+pub struct Downsample {
+    /// Interval spec of row ages, such as `1h..=365d/16`. Ages are bucketed in seconds,
+    /// whatever unit is used
+    #[structopt(long, default_value = "1h..=365d/16")]
+    spec: IntervalArg,
+
+    /// Time to measure ages from, as seconds since the epoch or an RFC 3339 date,
+    /// defaults to the current time
+    #[structopt(long)]
+    now: Option<Timestamp>,
+
+    /// Read rows from this file instead of stdin
+    #[structopt(long, parse(from_os_str))]
+    input: Option<PathBuf>,
+
+    /// Character separating the timestamp from the value
+    #[structopt(long, default_value = ",")]
+    delimiter: char,
+
+    /// Skip the first row of input
+    #[structopt(long)]
+    header: bool,
+}
+
+impl Runner for Downsample {
+    type Config = Options;
+
+    fn run(
+        &mut self,
+        dst: &mut dyn std::io::Write,
+        config: Option<Self::Config>,
+    ) -> Result<(), color_eyre::eyre::Report> {
+        let ages = AgeBuckets::new(self.spec)?;
+        let now = self.now.unwrap_or_else(Timestamp::now);
+        let reader: Box<dyn Read> = match self.input {
+            Some(ref path) => Box::new(BufReader::new(File::open(path)?)),
+            None => Box::new(BufReader::new(io::stdin())),
+        };
+
+        let mut delimiter = [0; 4];
+        let delimiter = self.delimiter.encode_utf8(&mut delimiter).as_bytes();
+        if delimiter.len() != 1 {
+            color_eyre::eyre::bail!("`{}` is not a single byte delimiter", self.delimiter)
+        }
+
+        let rows = read_rows(reader, delimiter[0], self.header)?;
+        let series = downsample(&ages, now, rows);
+
+        // Rows read back best as they were written
+        let format = config
+            .as_ref()
+            .and_then(|c| c.format())
+            .unwrap_or(Format::Csv);
+        let numbers = config.as_ref().map(|c| c.numbers()).unwrap_or_default();
+        let mut out = Output::new(dst, format, numbers);
+
+        for (bucket, aggregate) in series {
+            let row = aggregate.row();
+
+            out.row(match bucket {
+                Bucket::Index(bucket) => with_bounds(row, Some(bucket)),
+                Bucket::Overflow | Bucket::Young => with_bounds(row, None),
+            })?;
+        }
+
+        out.finish()
+    }
+}
+
+/// Where an aggregated row belongs
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Bucket {
+    /// Older than the interval
+    Overflow,
+    /// A bucket of the interval
+    Index(hanower::Bucket),
+    /// Younger than the interval, one per row
+    Young,
+}
+
+/// A row of the series, bucketed by its age in seconds
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Sample {
+    key: f64,
+    time: Timestamp,
+    value: f64,
+}
+
+impl Keyed for Sample {
+    fn key(&self) -> f64 {
+        self.key
+    }
+}
+
+/// The min, max, sum, count and last of the values of one bucket
+#[derive(Debug, Clone, PartialEq)]
+struct Aggregate {
+    count: u64,
+    min: f64,
+    max: f64,
+    sum: f64,
+    last: Sample,
+}
+
+impl Aggregate {
+    /// A row of the last value, timed at it, along with every other aggregate
+    fn row(&self) -> Row {
+        Row::new(self.last.value)
+            .with("time", Value::Text(self.last.time.to_string()))
+            .with("count", self.count)
+            .with("min", self.min)
+            .with("max", self.max)
+            .with("mean", self.sum / self.count as f64)
+    }
+}
+
+/// Reduces each bucket to an Aggregate of its values, with the library's reducers.
+/// Samples must be pushed from oldest to newest for `last` to be the newest
+#[derive(Debug, Clone, Copy)]
+struct Summary;
+
+impl Reducer<Sample> for Summary {
+    type State = (f64, f64, f64, u64, Sample);
+    type Output = Aggregate;
+
+    fn start(&self, bucket: &hanower::Bucket, sample: Sample) -> Self::State {
+        (
+            Min.start(bucket, sample.value),
+            Max.start(bucket, sample.value),
+            Sum.start(bucket, sample.value),
+            Reducer::<f64>::start(&Count, bucket, sample.value),
+            Last.start(bucket, sample),
+        )
+    }
+
+    fn push(&self, bucket: &hanower::Bucket, state: &mut Self::State, sample: Sample) {
+        let (min, max, sum, count, last) = state;
+
+        Min.push(bucket, min, sample.value);
+        Max.push(bucket, max, sample.value);
+        Sum.push(bucket, sum, sample.value);
+        Reducer::<f64>::push(&Count, bucket, count, sample.value);
+        Last.push(bucket, last, sample);
+    }
+
+    fn finish(&self, state: &Self::State) -> Vec<Aggregate> {
+        let (min, max, sum, count, last) = state;
+
+        vec![Aggregate {
+            count: *count,
+            min: *min,
+            max: *max,
+            sum: *sum,
+            last: *last,
+        }]
+    }
+}
+
+/// Reads one `<timestamp><delimiter><value>` row per line, skipping empty lines
+fn read_rows(
+    reader: Box<dyn Read>,
+    delimiter: u8,
+    header: bool,
+) -> Result<Vec<(Timestamp, f64)>, color_eyre::eyre::Report> {
+    let reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(header)
+        .flexible(true)
+        .from_reader(reader);
+    let mut rows = Vec::new();
+
+    for record in reader.into_records() {
+        let record = record?;
+        let line = record.position().map_or(0, |pos| pos.line());
+
+        let (time, value) = match (record.get(0), record.get(1)) {
+            (Some(time), Some(value)) => (time, value),
+            _ => color_eyre::eyre::bail!("line {}: expected a timestamp followed by a value", line),
+        };
+        let time: Timestamp = time
+            .parse()
+            .map_err(|e| color_eyre::eyre::eyre!("line {}: {}", line, e))?;
+        let value: f64 = value.trim().parse().map_err(|_| {
+            color_eyre::eyre::eyre!("line {}: invalid value (was: {})", line, value.trim())
+        })?;
+
+        rows.push((time, value));
+    }
+
+    Ok(rows)
+}
+
+/// Aggregates `rows` by their age relative to `now`, returning one Aggregate
+/// per bucket from oldest to youngest
+fn downsample(
+    ages: &AgeBuckets,
+    now: Timestamp,
+    mut rows: Vec<(Timestamp, f64)>,
+) -> Vec<(Bucket, Aggregate)> {
+    let interval = ages.interval;
+
+    // Oldest first, keeping input order between rows of the same time, so that
+    // the last row of each bucket is its newest
+    rows.sort_by(|(a, _), (b, _)| a.seconds().total_cmp(&b.seconds()));
+    let samples = rows.into_iter().map(|(time, value)| Sample {
+        // Rows from the future are as young as can be
+        key: (now.seconds() - time.seconds()).max(0.0),
+        time,
+        value,
+    });
+
+    let mut buckets = ByBucket::new(interval, Summary);
+    let mut young = Vec::new();
+    let mut overflow = Vec::new();
+    for sample in samples {
+        if !buckets.push(sample) {
+            match sample.key < interval.low() {
+                true => young.push(sample),
+                false => overflow.push(sample),
+            }
+        }
+    }
+
+    // Rows older than the interval share a single row, summarised as if they were a bucket
+    let older = overflow.split_first().map(|(&first, rest)| {
+        let bucket = interval
+            .bucket_bounds(interval.count() as usize - 1)
+            .expect("an Interval has at least one bucket");
+        let mut state = Summary.start(&bucket, first);
+        rest.iter()
+            .for_each(|&sample| Summary.push(&bucket, &mut state, sample));
+
+        Summary.finish(&state)
+    });
+
+    let mut indexed: Vec<(Bucket, Aggregate)> = buckets
+        .results()
+        .flat_map(|(bucket, aggregates)| {
+            aggregates
+                .into_iter()
+                .map(move |aggregate| (Bucket::Index(bucket), aggregate))
+        })
+        .collect();
+    indexed.reverse();
+
+    older
+        .into_iter()
+        .flatten()
+        .map(|aggregate| (Bucket::Overflow, aggregate))
+        .chain(indexed)
+        // Young rows are printed by time, rather than in input order
+        .chain(young.into_iter().map(|sample| {
+            let aggregate = Aggregate {
+                count: 1,
+                min: sample.value,
+                max: sample.value,
+                sum: sample.value,
+                last: sample,
+            };
+
+            (Bucket::Young, aggregate)
+        }))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::run_cli;
+
+    #[test]
+    fn aggregates_by_age() {
+        let ages = AgeBuckets::new("1d..=61d/2:linear".parse().unwrap()).unwrap();
+        let now: Timestamp = "2024-01-10".parse().unwrap();
+        let input = "2024-01-10,9\n2024-01-09T12:00:00Z,8\n2024-01-08,1\n2024-01-05,3\n2024-01-07,2\n2023-12-01,4\n2020-01-01,5\n2019-01-01,6\n";
+
+        let rows = read_rows(Box::new(input.as_bytes()), b',', false).unwrap();
+        let series = downsample(&ages, now, rows);
+        let summary: Vec<(u64, f64, f64, f64, f64)> = series
+            .iter()
+            .map(|(_, a)| (a.count, a.min, a.max, a.sum / a.count as f64, a.last.value))
+            .collect();
+
+        // Overflow, [31d, 61d), [1d, 31d) then two young rows
+        assert_eq!(
+            vec![
+                (2, 5.0, 6.0, 5.5, 5.0),
+                (1, 4.0, 4.0, 4.0, 4.0),
+                (3, 1.0, 3.0, 2.0, 1.0),
+                (1, 8.0, 8.0, 8.0, 8.0),
+                (1, 9.0, 9.0, 9.0, 9.0),
+            ],
+            summary
+        );
+        assert!(matches!(
+            series.iter().map(|(b, _)| *b).collect::<Vec<_>>()[..],
+            [
+                Bucket::Overflow,
+                Bucket::Index(_),
+                Bucket::Index(_),
+                Bucket::Young,
+                Bucket::Young
+            ]
+        ));
+    }
+
+    #[test]
+    fn last_is_newest() {
+        let ages = AgeBuckets::new("1d..=100d/1".parse().unwrap()).unwrap();
+        let now: Timestamp = "2024-01-10".parse().unwrap();
+        // Out of time order, with a tie broken by input order
+        let input = "2024-01-05,1\n2024-01-08,2\n2024-01-02,3\n2024-01-08,4\n2024-01-06,5\n";
+
+        let rows = read_rows(Box::new(input.as_bytes()), b',', false).unwrap();
+        let series = downsample(&ages, now, rows);
+
+        assert_eq!(1, series.len());
+        let (_, aggregate) = &series[0];
+        assert_eq!(
+            (5, 1.0, 5.0, 15.0, 4.0),
+            (
+                aggregate.count,
+                aggregate.min,
+                aggregate.max,
+                aggregate.sum,
+                aggregate.last.value
+            )
+        );
+        assert_eq!("2024-01-08T00:00:00Z", aggregate.last.time.to_string());
+    }
+
+    #[test]
+    fn read_rows_options() {
+        let input = "time;value\n2024-01-05; 1.5\n\n1704844800;-2\n";
+        let rows = read_rows(Box::new(input.as_bytes()), b';', true).unwrap();
+        assert_eq!(
+            vec![(1704412800.0, 1.5), (1704844800.0, -2.0)],
+            rows.iter()
+                .map(|(time, value)| (time.seconds(), *value))
+                .collect::<Vec<_>>()
+        );
+
+        for bad in &["2024-01-05\n", "2024-01-05,x\n", "yesterday,1\n"] {
+            assert!(
+                read_rows(Box::new(bad.as_bytes()), b',', false).is_err(),
+                "{:?}",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn prints_series() {
+        let path = std::env::temp_dir().join(format!("hanower-downsample-{}", std::process::id()));
+        std::fs::write(
+            &path,
+            "2024-01-10,9\n2024-01-08,1\n2024-01-05,3\n2023-12-01,4\n2019-01-01,6\n",
+        )
+        .unwrap();
+
+        let output = run_cli(&[
+            "downsample",
+            "--spec",
+            "1d..=61d/2:linear",
+            "--now",
+            "2024-01-10",
+            "--input",
+            path.to_str().unwrap(),
+        ]);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            "time,count,min,max,mean,bucket,low,high,value\n\
+             2019-01-01T00:00:00Z,1,6,6,6,,,,6\n\
+             2023-12-01T00:00:00Z,1,4,4,4,1,31d,61d,4\n\
+             2024-01-08T00:00:00Z,2,1,3,2,0,1d,31d,1\n\
+             2024-01-10T00:00:00Z,1,9,9,9,,,,9\n",
+            output.unwrap()
+        );
+    }
+
+    #[test]
+    fn labels_rows_with_their_bucket() {
+        let ages = AgeBuckets::new("1d..=100d/4".parse().unwrap()).unwrap();
+        let fence = ages.interval.fence(2).unwrap();
+        let now = Timestamp(fence);
+
+        // Aged exactly at the fence starting bucket 2, and halfway through bucket 3
+        let rows = vec![(Timestamp(fence - 5_000_000.0), 2.0), (Timestamp(0.0), 1.0)];
+        let series = downsample(&ages, now, rows);
+
+        let buckets: Vec<(usize, f64, f64)> = series
+            .iter()
+            .filter_map(|(bucket, _)| match bucket {
+                Bucket::Index(bucket) => Some((bucket.index(), bucket.low(), bucket.high())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                (3, ages.interval.fence(3).unwrap(), 8_640_000.0),
+                (2, fence, ages.interval.fence(3).unwrap())
+            ],
+            buckets
+        );
+
+        // Every row is older than an interval of negative ages
+        let negative = AgeBuckets::new("-5s..=-2s/3:linear".parse().unwrap()).unwrap();
+        let series = downsample(&negative, now, vec![(now, 1.0), (Timestamp(0.0), 2.0)]);
+        assert_eq!(1, series.len());
+        assert_eq!((Bucket::Overflow, 2), (series[0].0, series[0].1.count));
+
+        let path = std::env::temp_dir().join(format!("hanower-labels-{}", std::process::id()));
+        std::fs::write(&path, "2023-12-11,1\n2023-11-20T12:00:00Z,2\n").unwrap();
+
+        let output = run_cli(&[
+            "downsample",
+            "--spec",
+            "1d..=100d/4:linear",
+            "--now",
+            "2024-01-10",
+            "--input",
+            path.to_str().unwrap(),
+        ]);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            "time,count,min,max,mean,bucket,low,high,value\n\
+             2023-11-20T12:00:00Z,1,2,2,2,2,50.5d,75.25d,2\n\
+             2023-12-11T00:00:00Z,1,1,1,1,1,25.75d,50.5d,1\n",
+            output.unwrap()
+        );
+    }
+}
@@ -0,0 +1,295 @@
+/*
+ * This Source Code Form is subject to the terms of
+ * the Mozilla Public License, v. 2.0. If a copy of
+ * the MPL was not distributed with this file, You
+ * can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use super::*;
+use crate::output::{Format, Output, Row, Value};
+use hanower::{Interval, Rounding};
+use std::{
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+/// Picks commits at log-spaced distances along the first-parent history of a ref,
+/// dense near the ref and sparse in the past. Runs `git`, which must be installed
+#[derive(Debug, StructOpt)]
+pub struct GitSample {
+    /// Ref to walk back from, such as `main` or `v1.0`
+    #[structopt(long = "ref", default_value = "HEAD")]
+    rev: String,
+
+    /// Number of intervals to split the history into. Fewer commits are
+    /// printed when neighbouring fences round to the same commit
+    #[structopt(long, default_value = "10")]
+    count: u64,
+
+    /// Repository to sample, defaults to the one containing the current directory
+    #[structopt(long, parse(from_os_str))]
+    repo: Option<PathBuf>,
+}
+
+impl Runner for GitSample {
+    type Config = Options;
+
+    fn run(
+        &mut self,
+        dst: &mut dyn std::io::Write,
+        config: Option<Self::Config>,
+    ) -> Result<(), color_eyre::eyre::Report> {
+        let repo = self.repo.as_deref();
+        let depths = depths(history_len(repo, &self.rev)?, self.count)?;
+        let commits = commits_at(repo, &self.rev, &depths)?;
+
+        let format = config
+            .as_ref()
+            .and_then(|c| c.format())
+            .unwrap_or(Format::Lines);
+        let numbers = config.as_ref().map(|c| c.numbers()).unwrap_or_default();
+        let mut out = Output::new(dst, format, numbers);
+
+        for commit in commits {
+            out.row(
+                Row::new(Value::Text(commit.hash))
+                    .with("depth", commit.depth)
+                    .with("date", Value::Text(commit.date))
+                    .with("subject", Value::Text(commit.subject)),
+            )?;
+        }
+
+        out.finish()
+    }
+}
+
+/// A commit, along with its distance from the ref it was reached from
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Commit {
+    depth: u64,
+    hash: String,
+    /// Committer date, in strict ISO 8601
+    date: String,
+    subject: String,
+}
+
+fn git(repo: Option<&Path>) -> Command {
+    let mut command = Command::new("git");
+    if let Some(repo) = repo {
+        command.arg("-C").arg(repo);
+    }
+
+    command
+}
+
+/// Returns the number of commits in the first-parent history of `rev`, including itself
+fn history_len(repo: Option<&Path>, rev: &str) -> Result<u64, color_eyre::eyre::Report> {
+    let output = git(repo)
+        .args([
+            "rev-list",
+            "--first-parent",
+            "--count",
+            "--end-of-options",
+            rev,
+            "--",
+        ])
+        .output()?;
+
+    if !output.status.success() {
+        color_eyre::eyre::bail!(
+            "git rev-list failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+    }
+
+    let count = String::from_utf8_lossy(&output.stdout);
+    count
+        .trim()
+        .parse()
+        .map_err(|_| color_eyre::eyre::eyre!("unexpected output from git rev-list: {}", count))
+}
+
+/// Returns the sorted, distinct depths to pick out of a history of `len` commits,
+/// where `0` is the newest commit
+fn depths(len: u64, count: u64) -> Result<Vec<u64>, color_eyre::eyre::Report> {
+    match len {
+        0 => Ok(Vec::new()),
+        1 => Ok(vec![0]),
+        len => Ok(Interval::new(0.0, (len - 1) as f64, count)?
+            .integers(Rounding::Nearest)
+            .into_iter()
+            .map(|depth| depth as u64)
+            .collect()),
+    }
+}
+
+/// Reads the commits at `depths` (sorted) of the first-parent history of `rev`,
+/// stopping git once the last one has been read
+fn commits_at(
+    repo: Option<&Path>,
+    rev: &str,
+    depths: &[u64],
+) -> Result<Vec<Commit>, color_eyre::eyre::Report> {
+    let mut child = git(repo)
+        .args([
+            "log",
+            "--first-parent",
+            "--no-color",
+            "--format=%H%x00%cI%x00%s",
+            "--end-of-options",
+            rev,
+            "--",
+        ])
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let stdout = child.stdout.take().expect("stdout is piped");
+
+    let mut wanted = depths.iter().copied().peekable();
+    let mut commits = Vec::with_capacity(depths.len());
+
+    for (depth, line) in (0..).zip(BufReader::new(stdout).lines()) {
+        let line = line?;
+
+        match wanted.peek() {
+            Some(&next) if next == depth => {
+                wanted.next();
+            }
+            Some(_) => continue,
+            None => break,
+        }
+
+        let mut fields = line.splitn(3, '\0');
+        match (fields.next(), fields.next(), fields.next()) {
+            (Some(hash), Some(date), Some(subject)) => commits.push(Commit {
+                depth,
+                hash: hash.to_string(),
+                date: date.to_string(),
+                subject: subject.to_string(),
+            }),
+            _ => color_eyre::eyre::bail!("unexpected output from git log: {}", line),
+        }
+
+        if wanted.peek().is_none() {
+            break;
+        }
+    }
+
+    // The rest of the history is not needed
+    if wanted.peek().is_none() {
+        let _ = child.kill();
+        let _ = child.wait();
+
+        return Ok(commits);
+    }
+
+    match child.wait()? {
+        status if status.success() => color_eyre::eyre::bail!(
+            "history of `{}` ended before depth {}",
+            rev,
+            wanted.peek().copied().unwrap_or_default()
+        ),
+        status => color_eyre::eyre::bail!("git log failed: {}", status),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs, process};
+
+    /// A throwaway repository, removed on drop
+    struct Repo(PathBuf);
+
+    impl Repo {
+        fn new(name: &str) -> Self {
+            let path = env::temp_dir().join(format!("hanower-{}-{}", name, process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+
+            let repo = Self(path);
+            // `init -b` needs git 2.28
+            repo.git(&["init", "-q"]);
+            repo.git(&["symbolic-ref", "HEAD", "refs/heads/main"]);
+            repo
+        }
+
+        fn git(&self, args: &[&str]) {
+            let status = git(Some(&self.0))
+                .args(args)
+                .env("GIT_AUTHOR_NAME", "hanower")
+                .env("GIT_AUTHOR_EMAIL", "hanower@example.com")
+                .env("GIT_COMMITTER_NAME", "hanower")
+                .env("GIT_COMMITTER_EMAIL", "hanower@example.com")
+                .env("GIT_CONFIG_NOSYSTEM", "1")
+                // Missing, so that no user config applies on any platform
+                .env(
+                    "GIT_CONFIG_GLOBAL",
+                    self.0.join(".git").join("no-global-config"),
+                )
+                .status()
+                .unwrap();
+
+            assert!(status.success(), "git {:?}", args);
+        }
+
+        fn commit(&self, subject: &str) {
+            self.git(&["commit", "-q", "--allow-empty", "-m", subject]);
+        }
+    }
+
+    impl Drop for Repo {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn samples_first_parent_history() {
+        let repo = Repo::new("git-sample");
+        for n in 1..=5 {
+            repo.commit(&format!("main {}", n));
+        }
+        repo.git(&["checkout", "-q", "-b", "side"]);
+        repo.commit("side 1");
+        repo.git(&["checkout", "-q", "main"]);
+        repo.commit("main 6");
+        repo.git(&["merge", "-q", "--no-ff", "side", "-m", "merge side"]);
+
+        // merge side, main 6, main 5 .. main 1
+        let len = history_len(Some(&repo.0), "main").unwrap();
+        assert_eq!(7, len);
+
+        // Fences: 0, ~1.65, 6
+        let depths = depths(len, 2).unwrap();
+        assert_eq!(vec![0, 2, 6], depths);
+
+        let subjects: Vec<(u64, String)> = commits_at(Some(&repo.0), "main", &depths)
+            .unwrap()
+            .into_iter()
+            .map(|commit| (commit.depth, commit.subject))
+            .collect();
+        assert_eq!(
+            vec![
+                (0, "merge side".to_string()),
+                (2, "main 5".to_string()),
+                (6, "main 1".to_string())
+            ],
+            subjects
+        );
+
+        assert!(history_len(Some(&repo.0), "missing").is_err());
+    }
+
+    #[test]
+    fn refs_are_not_options() {
+        let repo = Repo::new("git-sample-options");
+        repo.commit("main 1");
+
+        let output = repo.0.join("output");
+        let rev = format!("--output={}", output.display());
+        assert!(history_len(Some(&repo.0), &rev).is_err());
+        assert!(commits_at(Some(&repo.0), &rev, &[0]).is_err());
+        assert!(!output.exists());
+    }
+}
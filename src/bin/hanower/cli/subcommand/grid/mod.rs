@@ -0,0 +1,208 @@
+/*
+ * This Source Code Form is subject to the terms of
+ * the Mozilla Public License, v. 2.0. If a copy of
+ * the MPL was not distributed with this file, You
+ * can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use super::*;
+use crate::output::{Format, Output, Row, Value};
+use args::{integral, IntervalArg};
+use hanower::{Axis, Grid, Unit};
+use std::str::FromStr;
+use structopt::clap::AppSettings::AllowLeadingHyphen;
+
+/// Prints every point of the cartesian product of several intervals, one per axis,
+/// such as `grid threads=1..=64/6 size=1KiB..=1MiB/4:linear`.
+///
+/// Points are numbered from 0 with the last axis varying fastest, so an
+/// interrupted run can be resumed with `--start`
+#[derive(Debug, StructOpt)]
+#[structopt(setting = AllowLeadingHyphen)]
+pub struct SubComGrid {
+    /// Index of the first point to print
+    #[structopt(long, default_value = "0")]
+    start: usize,
+
+    /// Print at most this many points
+    #[structopt(long)]
+    limit: Option<usize>,
+
+    /// Axes as `name=spec`, such as `threads=1..=64/6`, each with its own count and
+    /// spacing. Unnamed axes are called `axis1`, `axis2` and so on
+    #[structopt(required = true)]
+    axes: Vec<AxisArg>,
+}
+
+impl Runner for SubComGrid {
+    type Config = Options;
+
+    fn run(
+        &mut self,
+        dst: &mut dyn std::io::Write,
+        config: Option<Self::Config>,
+    ) -> Result<(), color_eyre::eyre::Report> {
+        let grid = Grid::new(
+            self.axes
+                .iter()
+                .enumerate()
+                .map(|(idx, axis)| axis.axis(idx))
+                .collect(),
+        )?;
+        let units: Vec<Option<Unit>> = self.axes.iter().map(|axis| axis.spec.unit).collect();
+
+        // A point is only fully described with its axes' names
+        let format = config
+            .as_ref()
+            .and_then(|c| c.format())
+            .unwrap_or(Format::Csv);
+        let numbers = config.as_ref().map(|c| c.numbers()).unwrap_or_default();
+        let mut out = Output::new(dst, format, numbers);
+
+        let points = grid
+            .iter()
+            .enumerate()
+            .skip(self.start)
+            .take(self.limit.unwrap_or(usize::MAX));
+
+        for (index, point) in points {
+            let mut values =
+                grid.axes()
+                    .iter()
+                    .zip(units.iter())
+                    .zip(point)
+                    .map(|((axis, unit), value)| {
+                        (axis.name().to_string(), value_of(axis, *unit, value))
+                    });
+
+            match out.structured() {
+                // One column per axis, with the last axis in place of the value
+                true => {
+                    let (name, last) = values.next_back().expect("a grid has at least one axis");
+                    let row = Row::new(last).named(name).with("index", index as u64);
+
+                    out.row(values.fold(row, |row, (name, value)| row.with(name, value)))?;
+                }
+                // Formats without metadata print each point as its own group, such as one line of words
+                false => {
+                    out.group(None)?;
+                    for (_, value) in values {
+                        out.row(Row::new(value))?;
+                    }
+                }
+            }
+        }
+
+        out.finish()
+    }
+}
+
+/// Prints values of an axis with integer bounds and no unit as integers, like `interval` does
+fn value_of(axis: &Axis, unit: Option<Unit>, value: f64) -> Value {
+    match unit {
+        Some(unit) => Value::Quantity(value, unit.family().best_for(value)),
+        None if integral(&axis.interval(), None) => Value::Whole(value),
+        None => Value::Number(value),
+    }
+}
+
+/// Axis name taken by the metadata column of each point
+const RESERVED: &str = "index";
+
+/// An axis given on the command line, such as `threads=1..=64/6`
+#[derive(Debug, Clone)]
+struct AxisArg {
+    name: Option<String>,
+    spec: IntervalArg,
+}
+
+impl AxisArg {
+    /// Names unnamed axes after their 0-based position `idx`
+    fn axis(&self, idx: usize) -> Axis {
+        let name = match self.name {
+            Some(ref name) => name.clone(),
+            None => format!("axis{}", idx + 1),
+        };
+
+        Axis::new(name, self.spec.interval)
+    }
+}
+
+impl FromStr for AxisArg {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Specs contain `=` too, as in `1..=64`, but never a name-like prefix before it
+        let (name, spec) = match s.find('=') {
+            Some(idx)
+                if idx > 0
+                    && s[..idx]
+                        .chars()
+                        .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+                    && !s[..idx].starts_with('-') =>
+            {
+                (Some(s[..idx].to_string()), &s[idx + 1..])
+            }
+            _ => (None, s),
+        };
+
+        if name.as_deref() == Some(RESERVED) {
+            return Err(format!(
+                "Invalid axis name. `{}` is reserved for the index of each point (was: {})",
+                RESERVED, s
+            ));
+        }
+
+        let spec = spec.parse().map_err(|e| format!("{}", e))?;
+
+        Ok(Self { name, spec })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::run_cli;
+
+    #[test]
+    fn parse_axes() {
+        let data = vec![
+            ("threads=1..=64/6", Some("threads"), "1..=64/6"),
+            ("1..=64/6", None, "1..=64/6"),
+            ("-19..=12/3:linear", None, "-19..=12/3:linear"),
+            ("-19..12/3:linear", None, "-19..=12/3:linear"),
+            (
+                "payload_size=1KiB..=1MiB/4",
+                Some("payload_size"),
+                "1024..=1048576/4",
+            ),
+        ];
+
+        for (input, name, spec) in data {
+            let axis: AxisArg = input.parse().unwrap();
+
+            assert_eq!(name, axis.name.as_deref(), "{}", input);
+            assert_eq!(spec, axis.spec.interval.to_string(), "{}", input);
+        }
+
+        assert!("threads=".parse::<AxisArg>().is_err());
+        assert!("threads".parse::<AxisArg>().is_err());
+        assert!("index=1..=64/6".parse::<AxisArg>().is_err());
+    }
+
+    #[test]
+    fn values_match_interval() {
+        let grid = run_cli(&["grid", "--format", "json", "value=1..=100/4"]).unwrap();
+        let interval = run_cli(&["interval", "--format", "json", "--spec", "1..=100/4"]).unwrap();
+        assert_eq!(interval, grid);
+
+        let grid = run_cli(&["grid", "t=1..=100/4", "u=1..=100/4"]).unwrap();
+        let interval = run_cli(&["interval", "--format", "csv", "--spec", "1..=100/4"]).unwrap();
+        assert_eq!(
+            interval.lines().nth(2).unwrap().split(',').nth(1),
+            grid.lines().nth(2).unwrap().split(',').nth(2)
+        );
+
+        assert!(run_cli(&["grid", "index=1..=4/2"]).is_err());
+    }
+}
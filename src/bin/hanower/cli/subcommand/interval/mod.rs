@@ -6,27 +6,53 @@
  */
 
 use super::*;
-use hanower::SIGNIFICANT;
+use crate::output::{Format, Output, Row, Value};
+use args::{integral, IntervalArg};
+use hanower::{Interval, Quantity, Spacing, Unit, SIGNIFICANT};
 use structopt::clap::AppSettings::AllowLeadingHyphen;
 
-// Helps handle Rust's odd floating point support
-const WORKAROUND: f64 = SIGNIFICANT / 1e3;
+// Helps handle Rust's odd floating point support, fences closer than this
+// (relative) distance to an integer are printed as that integer
+const WORKAROUND: f64 = 1e3 / SIGNIFICANT;
 
 /// Finds the `count` number of intervals in a range from `low` to `high`
 #[derive(Debug, StructOpt)]
 #[structopt(setting = AllowLeadingHyphen)]
 pub struct SubComInterval {
+    /// Interval spec, such as `1..=1024/10`, used instead of `low`, `high` and `--count`
+    #[structopt(long, allow_hyphen_values = true, conflicts_with_all = &["low", "high", "count"])]
+    spec: Option<IntervalArg>,
+
     /// Number of intervals, minimum of 2
     #[structopt(long, default_value = "2")]
     count: u64,
 
-    /// Start point of section from which to find intervals
-    #[structopt(allow_hyphen_values = true)]
-    low: i64,
+    /// Unit to print intervals in, such as `ms` or `KiB`, defaults to the unit family of the input
+    #[structopt(long)]
+    unit: Option<Unit>,
+
+    /// Start point of section from which to find intervals, such as `-3`, `250ms` or `4KiB`
+    #[structopt(allow_hyphen_values = true, required_unless = "spec")]
+    low: Option<Quantity>,
 
-    /// End point of section from which to find intervals
-    #[structopt(allow_hyphen_values = true)]
-    high: i64,
+    /// End point of section from which to find intervals, such as `12`, `30s` or `1GB`
+    #[structopt(allow_hyphen_values = true, required_unless = "spec")]
+    high: Option<Quantity>,
+}
+
+impl SubComInterval {
+    fn interval(&self) -> Result<(Interval, Option<Unit>), color_eyre::eyre::Report> {
+        match (self.spec, self.low, self.high) {
+            (Some(spec), _, _) => Ok((spec.interval, spec.unit)),
+            (None, Some(low), Some(high)) => Ok(Interval::from_quantities(
+                low,
+                high,
+                self.count,
+                Spacing::default(),
+            )?),
+            _ => unreachable!("clap requires `low` and `high` unless `--spec` is given"),
+        }
+    }
 }
 
 impl Runner for SubComInterval {
@@ -35,26 +61,90 @@ impl Runner for SubComInterval {
     fn run(
         &mut self,
         dst: &mut dyn std::io::Write,
-        _config: Option<Self::Config>,
+        config: Option<Self::Config>,
     ) -> Result<(), color_eyre::eyre::Report> {
-        let low = self.low as f64;
-        let high = self.high as f64;
-
-        let interval = hanower::Interval::new(low, high, self.count)?;
-
-        //for number in interval.intervals().map(|f| f.round() as i64) {
-        for number in interval.intervals() {
-            let res = number.abs() - number.round().abs();
-            // dbg!(res);
-            // Work around for Rust's atrocious floating point support
-            if (res / number).abs() < WORKAROUND {
-                write!(dst, "{:.0} ", number.round())?
-            } else {
-                write!(dst, "{} ", number)?
+        let (interval, input_unit) = self.interval()?;
+        // Integer bounds keep the historical integer output, suitable for piping to other tools
+        let integral = integral(&interval, input_unit);
+
+        if let (Some(unit), Some(input)) = (self.unit, input_unit) {
+            if Unit::common(input, unit).is_none() {
+                color_eyre::eyre::bail!(
+                    "`{}` cannot be used to print a range given in `{}`",
+                    unit,
+                    input
+                )
             }
         }
-        writeln!(dst)?;
 
-        Ok(())
+        let format = config
+            .as_ref()
+            .and_then(|c| c.format())
+            .unwrap_or(Format::Words);
+        let numbers = config.as_ref().map(|c| c.numbers()).unwrap_or_default();
+        let numbers = match integral && self.unit.is_none() && input_unit.is_none() {
+            true => numbers.or_decimals(Some(0)),
+            false => numbers,
+        };
+        let mut out = Output::new(dst, format, numbers);
+
+        for (index, number) in (0u64..).zip(interval.intervals()) {
+            // An explicit unit prints every fence in that unit, otherwise pick one per fence
+            let unit = match (self.unit, input_unit) {
+                (Some(unit), _) => Some(unit),
+                (None, Some(input)) => Some(input.family().best_for(number)),
+                (None, None) => None,
+            };
+
+            let value = match unit {
+                Some(unit) => Value::Quantity(number, unit),
+                // Work around for Rust's atrocious floating point support
+                None if ((number - number.round()) / number).abs() < WORKAROUND => {
+                    Value::Number(number.round())
+                }
+                None => Value::Number(number),
+            };
+
+            out.row(Row::new(value).with("index", index))?;
+        }
+
+        out.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::run_cli;
+
+    #[test]
+    fn negative_bounds() {
+        assert_eq!(
+            "-10 -6 10\n",
+            run_cli(&["interval", "--", "-10", "10"]).unwrap()
+        );
+        assert_eq!(
+            "-20 -18 -10\n",
+            run_cli(&["interval", "-20", "-10"]).unwrap()
+        );
+    }
+
+    #[test]
+    fn integral_and_float_output() {
+        // Integer bounds print integer fences, even where `f64` misses by a hair
+        assert_eq!("1 10 100\n", run_cli(&["interval", "1", "100"]).unwrap());
+        assert_eq!(
+            "1000 1099 10992 1000000\n",
+            run_cli(&["interval", "--count", "3", "1e3", "1e6"]).unwrap()
+        );
+
+        assert_eq!(
+            "0.5 1.232050807569 2.5\n",
+            run_cli(&["interval", "0.5", "2.5"]).unwrap()
+        );
+        // Fences a hair away from an integer print as that integer, see WORKAROUND
+        assert_eq!(
+            "0.1 0.2 0.3 0.4 0.5 0.6 0.7 0.8 0.9 1 1.1\n",
+            run_cli(&["interval", "--spec", "0.1..=1.1/10:linear"]).unwrap()
+        );
     }
 }
@@ -8,8 +8,30 @@
 use crate::{config::Options, run::Runner};
 use structopt::StructOpt;
 
+mod ages;
+mod args;
+mod codegen;
+mod downsample;
+mod git_sample;
+mod grid;
 mod interval;
+mod pick;
+mod probe;
 mod range;
+mod retain;
+mod rotate;
+mod snap;
+mod sweep;
 
+pub use codegen::Codegen;
+pub use downsample::Downsample;
+pub use git_sample::GitSample;
+pub use grid::SubComGrid;
 pub use interval::SubComInterval;
+pub use pick::Pick;
+pub use probe::Probe;
 pub use range::Range;
+pub use retain::Retain;
+pub use rotate::Rotate;
+pub use snap::Snap;
+pub use sweep::Sweep;
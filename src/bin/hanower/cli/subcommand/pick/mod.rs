@@ -0,0 +1,206 @@
+/*
+ * This Source Code Form is subject to the terms of
+ * the Mozilla Public License, v. 2.0. If a copy of
+ * the MPL was not distributed with this file, You
+ * can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use super::*;
+use crate::output::{Format, Output, Row, Value};
+use hanower::{Interval, Rounding};
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Read},
+    path::PathBuf,
+};
+
+/// Prints the lines of a file at log-spaced line numbers, from 1 to the last line.
+///
+/// The file is read twice, once to count its lines and once to print them, so
+/// it never needs to fit in memory
+#[derive(Debug, StructOpt)]
+pub struct Pick {
+    /// File to pick lines from
+    #[structopt(long, parse(from_os_str))]
+    lines: PathBuf,
+
+    /// Number of intervals to split the line numbers into. Fewer lines are
+    /// printed when neighbouring fences round to the same line
+    #[structopt(long, default_value = "10")]
+    count: u64,
+
+    /// Count line numbers back from the last line, so lines near the end are dense
+    #[structopt(long)]
+    from_end: bool,
+}
+
+impl Runner for Pick {
+    type Config = Options;
+
+    fn run(
+        &mut self,
+        dst: &mut dyn std::io::Write,
+        config: Option<Self::Config>,
+    ) -> Result<(), color_eyre::eyre::Report> {
+        let total = count_lines(File::open(&self.lines)?)?;
+        let positions = positions(total, self.count, self.from_end)?;
+
+        // Whole lines only read well one per line
+        let format = config
+            .as_ref()
+            .and_then(|c| c.format())
+            .unwrap_or(Format::Lines);
+        let numbers = config.as_ref().map(|c| c.numbers()).unwrap_or_default();
+        let mut out = Output::new(dst, format, numbers);
+
+        let reader = BufReader::new(File::open(&self.lines)?);
+        for (line_no, line) in picked(reader, &positions) {
+            out.row(Row::new(Value::Text(line?)).with("line", line_no))?;
+        }
+
+        out.finish()
+    }
+}
+
+/// Counts the lines in `reader`, including a last line with no trailing newline
+fn count_lines(mut reader: impl Read) -> io::Result<u64> {
+    let mut buffer = vec![0; 64 * 1024];
+    let mut lines = 0;
+    let mut last = b'\n';
+
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+
+        lines += buffer[..read].iter().filter(|&&b| b == b'\n').count() as u64;
+        last = buffer[read - 1];
+    }
+
+    match last {
+        b'\n' => Ok(lines),
+        _ => Ok(lines + 1),
+    }
+}
+
+/// Returns the sorted, distinct line numbers to pick out of `total` lines
+fn positions(total: u64, count: u64, from_end: bool) -> Result<Vec<u64>, color_eyre::eyre::Report> {
+    let mut positions: Vec<u64> = match total {
+        0 => Vec::new(),
+        1 => vec![1],
+        total => Interval::new(1.0, total as f64, count)?
+            .integers(Rounding::Nearest)
+            .into_iter()
+            .map(|position| position as u64)
+            .collect(),
+    };
+
+    if from_end {
+        positions = positions.into_iter().rev().map(|p| total + 1 - p).collect();
+    }
+
+    Ok(positions)
+}
+
+/// Lazily reads the lines at `positions` (sorted, 1-based) out of `reader`,
+/// stopping after the last one
+fn picked<'a>(
+    reader: impl BufRead + 'a,
+    positions: &'a [u64],
+) -> impl Iterator<Item = (u64, io::Result<String>)> + 'a {
+    let wanted = positions.iter().copied().peekable();
+
+    (1..)
+        .zip(reader.split(b'\n'))
+        .scan(wanted, |wanted, (line_no, line)| {
+            // Ends the iterator once every position has been read, but never hides an error.
+            // Errors in unwanted lines leave the positions still to be read as they are
+            let due = *wanted.peek()? == line_no;
+            if !due && line.is_ok() {
+                return Some(None);
+            }
+            if due {
+                wanted.next();
+            }
+
+            let line = line.map(|mut line| {
+                if line.last() == Some(&b'\r') {
+                    line.pop();
+                }
+                String::from_utf8_lossy(&line).into_owned()
+            });
+
+            Some(Some((line_no, line)))
+        })
+        .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_log_spaced_lines() {
+        let text: String = (1..=100).map(|n| format!("line {}\n", n)).collect();
+        assert_eq!(100, count_lines(text.as_bytes()).unwrap());
+        assert_eq!(
+            101,
+            count_lines(format!("{}last", text).as_bytes()).unwrap()
+        );
+
+        let positions = positions(100, 2, false).unwrap();
+        assert_eq!(vec![1, 10, 100], positions);
+        assert_eq!(vec![1, 91, 100], super::positions(100, 2, true).unwrap());
+
+        let lines: Vec<(u64, String)> = picked(text.as_bytes(), &positions)
+            .map(|(line_no, line)| (line_no, line.unwrap()))
+            .collect();
+        assert_eq!(
+            vec![
+                (1, "line 1".to_string()),
+                (10, "line 10".to_string()),
+                (100, "line 100".to_string())
+            ],
+            lines
+        );
+    }
+
+    #[test]
+    fn errors_keep_positions() {
+        /// Reads each chunk in turn, failing where there is no chunk
+        struct Chunks(Vec<Option<&'static [u8]>>);
+
+        impl io::Read for Chunks {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                match self.0.is_empty() {
+                    true => Ok(0),
+                    false => match self.0.remove(0) {
+                        Some(chunk) => {
+                            buf[..chunk.len()].copy_from_slice(chunk);
+                            Ok(chunk.len())
+                        }
+                        None => Err(io::Error::new(io::ErrorKind::InvalidData, "bad line")),
+                    },
+                }
+            }
+        }
+
+        let reader =
+            io::BufReader::new(Chunks(vec![Some(b"a\n"), None, Some(b"b\n"), Some(b"c\n")]));
+        let lines: Vec<(u64, Result<String, io::ErrorKind>)> = picked(reader, &[3, 4])
+            .map(|(line_no, line)| (line_no, line.map_err(|e| e.kind())))
+            .collect();
+
+        assert_eq!(
+            vec![
+                (2, Err(io::ErrorKind::InvalidData)),
+                (3, Ok("b".to_string())),
+                (4, Ok("c".to_string()))
+            ],
+            lines
+        );
+    }
+}
@@ -0,0 +1,459 @@
+/*
+ * This Source Code Form is subject to the terms of
+ * the Mozilla Public License, v. 2.0. If a copy of
+ * the MPL was not distributed with this file, You
+ * can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use super::*;
+use crate::output::{Format, Output, Row, Value};
+use args::{integral, IntervalArg};
+use hanower::{Interval, Quantity, Rounding, Spacing, Unit};
+use std::str::FromStr;
+use structopt::clap::AppSettings::AllowNegativeNumbers;
+use sweep::{execute, substitute};
+
+/// Finds the value at which a command starts to fail, such as
+/// `probe 1 1GiB -- ./upload --size {}`, printing the last value it passed at
+/// and the first it failed at.
+///
+/// The command is run at each fence of the interval, from low to high, until it
+/// fails (exits non-zero). The bucket it failed in is then refined down to
+/// `--tolerance`. The command is assumed to pass below some value and fail above it
+#[derive(Debug, StructOpt)]
+#[structopt(setting = AllowNegativeNumbers)]
+pub struct Probe {
+    /// Interval spec, such as `1..=4096/12`, used instead of `low`, `high` and `--count`
+    #[structopt(long, allow_hyphen_values = true, conflicts_with_all = &["low", "high", "count"])]
+    spec: Option<IntervalArg>,
+
+    /// Number of intervals
+    #[structopt(long, default_value = "10")]
+    count: u64,
+
+    /// How to refine the failing bucket: `bisect`, or `nested` to split it into
+    /// `--count` even buckets at a time
+    #[structopt(long, default_value = "bisect")]
+    refine: Refine,
+
+    /// Largest gap to leave between the pass and the fail, such as `1` or `4KiB`.
+    /// Defaults to 1 for integer bounds, and to 0.1% of the failing value otherwise
+    #[structopt(long)]
+    tolerance: Option<Quantity>,
+
+    /// Text in the command to replace with each value
+    #[structopt(long, default_value = "{}")]
+    placeholder: String,
+
+    /// Print each value as it is tried, along with whether it passed, to stderr
+    #[structopt(long)]
+    trace: bool,
+
+    /// Start point of the probe, such as `1` or `4KiB`
+    #[structopt(allow_hyphen_values = true, required_unless = "spec")]
+    low: Option<Quantity>,
+
+    /// End point of the probe, such as `4096` or `1GiB`
+    #[structopt(allow_hyphen_values = true, required_unless = "spec")]
+    high: Option<Quantity>,
+
+    /// Command to run, along with its arguments, given after `--`
+    #[structopt(last = true, required = true)]
+    command: Vec<String>,
+}
+
+impl Probe {
+    /// Returns the Interval to probe, along with the unit it was given in
+    fn interval(&self) -> Result<(Interval, Option<Unit>), color_eyre::eyre::Report> {
+        match (self.spec, self.low, self.high) {
+            (Some(spec), _, _) => Ok((spec.interval, spec.unit)),
+            (None, Some(low), Some(high)) => Ok(Interval::from_quantities(
+                low,
+                high,
+                self.count,
+                Spacing::default(),
+            )?),
+            _ => unreachable!("clap requires `low` and `high` unless `--spec` is given"),
+        }
+    }
+
+    /// Returns the tolerance in base units, refusing one in units the interval is not in.
+    /// A plain number is taken to be in base units
+    fn tolerance(&self, unit: Option<Unit>) -> Result<Option<f64>, color_eyre::eyre::Report> {
+        match (self.tolerance, unit) {
+            (Some(tolerance), unit)
+                if tolerance
+                    .unit()
+                    .is_some_and(|t| unit.and_then(|u| Unit::common(t, u)).is_none()) =>
+            {
+                color_eyre::eyre::bail!(
+                    "Invalid tolerance. Ensure `--tolerance` is in the units of the interval (was: {})",
+                    tolerance
+                )
+            }
+            (tolerance, _) => Ok(tolerance.map(|t| t.value())),
+        }
+    }
+}
+
+impl Runner for Probe {
+    type Config = Options;
+
+    fn run(
+        &mut self,
+        dst: &mut dyn std::io::Write,
+        config: Option<Self::Config>,
+    ) -> Result<(), color_eyre::eyre::Report> {
+        let (interval, unit) = self.interval()?;
+        // Whole numbers and byte sizes, such as `4KiB`, are probed one unit at a time
+        let integral = integral(&interval, unit);
+
+        let format = config
+            .as_ref()
+            .and_then(|c| c.format())
+            .unwrap_or(Format::Words);
+        let numbers = config.as_ref().map(|c| c.numbers()).unwrap_or_default();
+        let numbers = match integral && unit.is_none() {
+            true => numbers.or_decimals(Some(0)),
+            false => numbers,
+        };
+        let mut out = Output::new(dst, format, numbers);
+
+        let search = Search {
+            interval,
+            integral,
+            refine: self.refine,
+            tolerance: self.tolerance(unit)?,
+        };
+        let boundary = search.run(|value| {
+            // The command gets the value in base units, whatever the number format
+            let command = substitute(&self.command, &self.placeholder, &value.to_string());
+            let outcome = execute(&command).map_err(|e| {
+                color_eyre::eyre::eyre!("failed to run `{}`: {}", command.join(" "), e)
+            })?;
+            let passed = outcome.status == Some(0);
+
+            if self.trace {
+                eprintln!(
+                    "{}: {} in {:?}",
+                    numbers.format(value, ""),
+                    if passed { "pass" } else { "fail" },
+                    outcome.duration
+                );
+            }
+
+            Ok(passed)
+        })?;
+
+        let value = |value: Option<f64>| match (value, unit) {
+            (Some(value), Some(unit)) => Value::Quantity(value, unit.family().best_for(value)),
+            (value, None) => value.into(),
+            (None, _) => Value::Missing,
+        };
+
+        out.row(
+            Row::new(value(boundary.fail))
+                .with("probes", boundary.probes)
+                .with("pass", value(boundary.pass)),
+        )?;
+
+        out.finish()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Refine {
+    /// Halve the gap between the pass and the fail
+    Bisect,
+    /// Split the failing bucket into as many even buckets as the interval has
+    Nested,
+}
+
+impl FromStr for Refine {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "bisect" => Ok(Self::Bisect),
+            "nested" => Ok(Self::Nested),
+            other => Err(format!(
+                "Unknown refinement. Ensure it is one of `bisect` or `nested` (was: {})",
+                other
+            )),
+        }
+    }
+}
+
+/// Where a command flipped from passing to failing
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Boundary {
+    /// Largest value the command passed at, if any
+    pass: Option<f64>,
+    /// Smallest value the command failed at, if any
+    fail: Option<f64>,
+    /// Number of times the command was run
+    probes: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Search {
+    interval: Interval,
+    /// Whether only integers should be tried
+    integral: bool,
+    refine: Refine,
+    tolerance: Option<f64>,
+}
+
+impl Search {
+    /// Looks for the Boundary of `passes`, calling it with increasing values until
+    /// it first returns false, then with values between the last pass and the first fail
+    fn run<F>(&self, mut passes: F) -> Result<Boundary, color_eyre::eyre::Report>
+    where
+        F: FnMut(f64) -> Result<bool, color_eyre::eyre::Report>,
+    {
+        let mut boundary = Boundary {
+            pass: None,
+            fail: None,
+            probes: 0,
+        };
+
+        let mut test = |boundary: &mut Boundary, value: f64| {
+            boundary.probes += 1;
+
+            passes(value).map(|passed| match passed {
+                true => boundary.pass = Some(value),
+                false => boundary.fail = Some(value),
+            })
+        };
+
+        for value in self.values(self.interval) {
+            test(&mut boundary, value)?;
+
+            if boundary.fail.is_some() {
+                break;
+            }
+        }
+
+        while let (Some(pass), Some(fail)) = (boundary.pass, boundary.fail) {
+            let tolerance = match (self.tolerance, self.integral) {
+                (Some(tolerance), _) => tolerance,
+                (None, true) => 1.0,
+                (None, false) => fail.abs() * 1e-3,
+            };
+            if fail - pass <= tolerance {
+                break;
+            }
+
+            let candidates = match self.refine {
+                Refine::Bisect => match self.integral {
+                    true => vec![pass + ((fail - pass) / 2.0).floor()],
+                    false => vec![pass + (fail - pass) / 2.0],
+                },
+                Refine::Nested => {
+                    // Log spacing would crowd the fences next to the pass
+                    match Interval::with_spacing(pass, fail, self.interval.count(), Spacing::Linear)
+                    {
+                        Ok(bucket) => self.values(bucket),
+                        // Too narrow to split any further
+                        Err(_) => break,
+                    }
+                }
+            };
+
+            // Only values strictly inside the bucket tell us anything new
+            let inside: Vec<f64> = candidates
+                .into_iter()
+                .filter(|&value| pass < value && value < fail)
+                .collect();
+            if inside.is_empty() {
+                break;
+            }
+
+            for value in inside {
+                test(&mut boundary, value)?;
+
+                if boundary.fail != Some(fail) {
+                    break;
+                }
+            }
+        }
+
+        Ok(boundary)
+    }
+
+    /// The values to try across `interval`, from low to high
+    fn values(&self, interval: Interval) -> Vec<f64> {
+        match self.integral {
+            true => interval
+                .integers(Rounding::Nearest)
+                .into_iter()
+                .map(|value| value as f64)
+                .collect(),
+            false => interval.intervals().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(unix)]
+    use crate::cli::run_cli;
+
+    fn search(low: f64, high: f64, refine: Refine) -> Search {
+        Search {
+            interval: Interval::new(low, high, 4).unwrap(),
+            integral: low.fract() == 0.0 && high.fract() == 0.0,
+            refine,
+            tolerance: None,
+        }
+    }
+
+    #[test]
+    fn finds_boundary() {
+        for &refine in &[Refine::Bisect, Refine::Nested] {
+            let mut tried = Vec::new();
+            let boundary = search(1.0, 4096.0, refine)
+                .run(|value| {
+                    tried.push(value);
+                    Ok(value <= 1000.0)
+                })
+                .unwrap();
+
+            assert_eq!(Some(1000.0), boundary.pass, "{:?}", refine);
+            assert_eq!(Some(1001.0), boundary.fail, "{:?}", refine);
+            assert_eq!(tried.len() as u64, boundary.probes);
+        }
+
+        let boundary = search(0.5, 10.0, Refine::Bisect)
+            .run(|value| Ok(value < 3.0))
+            .unwrap();
+        let (pass, fail) = (boundary.pass.unwrap(), boundary.fail.unwrap());
+        assert!(
+            pass < 3.0 && fail >= 3.0 && fail - pass <= 3e-3,
+            "{:?}",
+            boundary
+        );
+    }
+
+    #[test]
+    fn boundary_outside_interval() {
+        let never = search(1.0, 100.0, Refine::Bisect)
+            .run(|_| Ok(false))
+            .unwrap();
+        assert_eq!((None, Some(1.0), 1), (never.pass, never.fail, never.probes));
+
+        let always = search(1.0, 100.0, Refine::Bisect)
+            .run(|_| Ok(true))
+            .unwrap();
+        assert_eq!((Some(100.0), None), (always.pass, always.fail));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn passes_raw_values() {
+        let path = std::env::temp_dir().join(format!("hanower-probe-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let script = format!("echo \"$0\" >> '{}'; test \"$0\" -le 4200", path.display());
+
+        // The number format is only for the output row
+        let output = run_cli(&[
+            "probe",
+            "--thousands",
+            ",",
+            "--decimals",
+            "1",
+            "--format",
+            "csv",
+            "1",
+            "10000",
+            "--",
+            "sh",
+            "-c",
+            &script,
+            "{}",
+        ])
+        .unwrap();
+        let argv = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        let mut expected = String::new();
+        let search = Search {
+            interval: Interval::new(1.0, 10000.0, 10).unwrap(),
+            ..search(1.0, 10000.0, Refine::Bisect)
+        };
+        search
+            .run(|value| {
+                expected.push_str(&format!("{}\n", value));
+                Ok(value <= 4200.0)
+            })
+            .unwrap();
+        let probes = expected.lines().count();
+
+        assert_eq!(expected, argv);
+        assert_eq!(
+            format!("probes,pass,value\n{},\"4,200.0\",\"4,201.0\"\n", probes),
+            output
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn probes_fractional_durations() {
+        let output = run_cli(&[
+            "probe",
+            "--format",
+            "csv",
+            "--spec",
+            "1s..=5s/4",
+            "--tolerance",
+            "0.5",
+            "--",
+            "sh",
+            "-c",
+            "test \"${0%%.*}\" -lt 2",
+            "{}",
+        ])
+        .unwrap();
+
+        let row: Vec<&str> = output.lines().nth(1).unwrap().split(',').collect();
+        let (pass, fail): (f64, f64) = (
+            row[1].trim_end_matches('s').parse().unwrap(),
+            row[2].trim_end_matches('s').parse().unwrap(),
+        );
+        assert!(
+            pass < 2.0 && 2.0 <= fail && fail - pass <= 0.5,
+            "{}",
+            output
+        );
+        assert!(fail.fract() != 0.0, "{}", output);
+    }
+
+    #[test]
+    fn tolerance_in_interval_units() {
+        let probe = |args: &[&str]| {
+            let args: Vec<&str> = ["probe"].iter().chain(args).copied().collect();
+            Probe::from_iter_safe(args).unwrap()
+        };
+        let bytes = Some("KiB".parse::<Unit>().unwrap());
+
+        for &(tolerance, unit, expected) in &[
+            ("4KiB", bytes, Some(4096.0)),
+            ("8B", bytes, Some(8.0)),
+            ("5", bytes, Some(5.0)),
+            ("5", None, Some(5.0)),
+        ] {
+            let probe = probe(&["--tolerance", tolerance, "1", "2", "--", "true"]);
+            assert_eq!(expected, probe.tolerance(unit).unwrap(), "{}", tolerance);
+        }
+
+        for &(tolerance, unit) in &[("5s", bytes), ("1MB", bytes), ("5s", None)] {
+            let probe = probe(&["--tolerance", tolerance, "1", "2", "--", "true"]);
+            assert!(probe.tolerance(unit).is_err(), "{}", tolerance);
+        }
+        assert_eq!(
+            None,
+            probe(&["1", "2", "--", "true"]).tolerance(bytes).unwrap()
+        );
+    }
+}
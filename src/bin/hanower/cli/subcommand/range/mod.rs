@@ -6,30 +6,80 @@
  */
 
 use super::*;
+use crate::{
+    input::{InputArgs, InputError},
+    output::{Format, Output, Row, Value},
+};
+use args::IntervalArg;
+use hanower::{ByBucket, Interval, Keyed, Quantity, Reducer, Spacing, UnitFamily};
+use select::Select;
+use std::{
+    collections::BTreeMap,
+    num::NonZeroU64,
+    time::{Duration, Instant},
+};
+use structopt::clap::{AppSettings::AllowNegativeNumbers, ArgGroup};
 
-use bybucket::ByBucket;
-
-mod buckets;
-mod bybucket;
+mod select;
 
 #[derive(Debug, StructOpt)]
+#[structopt(setting = AllowNegativeNumbers, group = ArgGroup::with_name("selector"))]
 pub struct Range {
-    #[structopt(long, default_value = "0")]
-    start: i64,
+    /// Interval spec, such as `0..=256/2`, used instead of `--start`, `--end` and `--count`
+    #[structopt(long, conflicts_with_all = &["start", "end", "count"])]
+    spec: Option<IntervalArg>,
+
+    #[structopt(long, default_value = "0", allow_hyphen_values = true)]
+    start: Quantity,
 
-    #[structopt(long, default_value = "256")]
-    end: i64,
+    #[structopt(long, default_value = "256", allow_hyphen_values = true)]
+    end: Quantity,
 
     #[structopt(long, default_value = "2")]
     count: u64,
 
-    #[structopt(short = "M", long = "maxByBucket", conflicts_with = "min-by-bucket")]
+    /// Same as `--select max`
+    #[structopt(short = "M", long = "maxByBucket", group = "selector")]
     max_by_bucket: bool,
 
-    #[structopt(short = "m", long = "minByBucket")]
+    /// Same as `--select min`
+    #[structopt(short = "m", long = "minByBucket", group = "selector")]
     min_by_bucket: bool,
 
-    range: Vec<i64>,
+    /// Selector or aggregate applied to each bucket: `first`, `last`, `min`, `max`, `median`,
+    /// `mean`, `sum`, `count`, `nearest-low`, `nearest-high` or `top:K`
+    #[structopt(long, group = "selector")]
+    select: Option<Select>,
+
+    /// Print the current selection after every N input values, as well as at the end
+    #[structopt(long, requires = "selector")]
+    emit_every: Option<NonZeroU64>,
+
+    /// Print the current selection once this much time has passed, such as `5s`,
+    /// checked as each input value arrives
+    #[structopt(long, requires = "selector")]
+    emit_interval: Option<Quantity>,
+
+    /// Print each bucket's bounds followed by every input value in it, in input order,
+    /// along with the values below and above the interval
+    #[structopt(long, conflicts_with_all = &["selector", "records"])]
+    group: bool,
+
+    /// Also print buckets which no input value fell into, see `--group`
+    #[structopt(long, requires = "group")]
+    empty: bool,
+
+    /// Select whole lines of input by their `--column` or `--json-pointer` key,
+    /// printing each selected line as it was read
+    #[structopt(long, conflicts_with = "range")]
+    records: bool,
+
+    #[structopt(flatten)]
+    input: InputArgs,
+
+    /// Values to select from, such as `-3`, `0.25` or `1e6`, read from stdin or `--input` if empty
+    #[structopt(allow_hyphen_values = true, conflicts_with = "input")]
+    range: Vec<f64>,
 }
 
 impl Runner for Range {
@@ -38,29 +88,401 @@ impl Runner for Range {
     fn run(
         &mut self,
         dst: &mut dyn std::io::Write,
-        _config: Option<Self::Config>,
+        config: Option<Self::Config>,
     ) -> Result<(), color_eyre::eyre::Report> {
-        let interval = hanower::Interval::new(self.start as f64, self.end as f64, self.count)?;
+        let interval = match self.spec {
+            Some(spec) => spec.interval,
+            None => {
+                Interval::from_quantities(self.start, self.end, self.count, Spacing::default())?.0
+            }
+        };
 
-        let output = if self.max_by_bucket {
-            ByBucket::new(interval).select_max(self.range.iter().copied())
+        let select = if self.max_by_bucket {
+            Some(Select::Max)
         } else if self.min_by_bucket {
-            ByBucket::new(interval).select_min(self.range.iter().copied())
+            Some(Select::Min)
+        } else {
+            self.select
+        };
+
+        // Whole records only read well one per line
+        let format = config
+            .as_ref()
+            .and_then(|c| c.format())
+            .unwrap_or(match self.records {
+                true => Format::Lines,
+                false => Format::Words,
+            });
+        let numbers = config.as_ref().map(|c| c.numbers()).unwrap_or_default();
+        let mut out = Output::new(dst, format, numbers);
+
+        if self.records {
+            self.select_records(&mut out, interval, select)?;
+
+            return out.finish();
+        }
+
+        let input: Box<dyn Iterator<Item = Result<f64, InputError>>> = match self.range.is_empty() {
+            true => self.input.values()?,
+            false => Box::new(self.range.clone().into_iter().map(Ok)),
+        };
+
+        if self.group {
+            self.group(&mut out, interval, input)?;
+
+            return out.finish();
+        }
+
+        match select {
+            Some(select) => self.select_by_bucket(&mut out, interval, select, input),
+            None => {
+                for item in input {
+                    let item = item?;
+
+                    if interval.low() <= item && interval.high() >= item {
+                        out.row(Row::new(item))?;
+                    }
+                }
+
+                Ok(())
+            }
+        }?;
+
+        out.finish()
+    }
+}
+
+impl Range {
+    /// Like `run`, but keeps and prints whole input records rather than bare values
+    fn select_records(
+        &self,
+        out: &mut Output,
+        interval: Interval,
+        select: Option<Select>,
+    ) -> Result<(), color_eyre::eyre::Report> {
+        let input = self.input.records()?;
+
+        match select {
+            Some(Select::Mean) | Some(Select::Sum) | Some(Select::Count) => {
+                color_eyre::eyre::bail!(
+                    "`mean`, `sum` and `count` aggregate values, so cannot be used with `--records`"
+                )
+            }
+            Some(select) => self.select_by_bucket(out, interval, select, input),
+            None => {
+                for record in input {
+                    let record = record?;
+
+                    if interval.low() <= record.key && interval.high() >= record.key {
+                        let key = record.key;
+                        out.row(Row::new(record).with("key", key))?;
+                    }
+                }
+
+                Ok(())
+            }
+        }
+    }
+
+    /// Streams every input item through the reducer `select` names, see `reduce`
+    fn select_by_bucket<T>(
+        &self,
+        out: &mut Output,
+        interval: Interval,
+        select: Select,
+        input: Box<dyn Iterator<Item = Result<T, InputError>>>,
+    ) -> Result<(), color_eyre::eyre::Report>
+    where
+        T: Keyed + Clone + Into<Value>,
+    {
+        match select {
+            Select::First => self.reduce(out, input, ByBucket::new(interval, hanower::First)),
+            Select::Last => self.reduce(out, input, ByBucket::new(interval, hanower::Last)),
+            Select::Min => self.reduce(out, input, ByBucket::new(interval, hanower::Min)),
+            Select::Max => self.reduce(out, input, ByBucket::new(interval, hanower::Max)),
+            Select::Median => self.reduce(out, input, ByBucket::new(interval, hanower::Median)),
+            Select::Mean => self.reduce(out, input, ByBucket::new(interval, hanower::Mean)),
+            Select::Sum => self.reduce(out, input, ByBucket::new(interval, hanower::Sum)),
+            Select::Count => self.reduce(out, input, ByBucket::new(interval, hanower::Count)),
+            Select::NearestLow => {
+                self.reduce(out, input, ByBucket::new(interval, hanower::NearestLow))
+            }
+            Select::NearestHigh => {
+                self.reduce(out, input, ByBucket::new(interval, hanower::NearestHigh))
+            }
+            Select::TopK(k) => self.reduce(out, input, ByBucket::new(interval, hanower::TopK(k))),
+        }
+    }
+
+    /// Collects every input value under the bucket it falls into, printing one group per bucket
+    fn group(
+        &self,
+        out: &mut Output,
+        interval: Interval,
+        input: Box<dyn Iterator<Item = Result<f64, InputError>>>,
+    ) -> Result<(), color_eyre::eyre::Report> {
+        let mut underflow = Vec::new();
+        let mut overflow = Vec::new();
+        let mut buckets: BTreeMap<usize, Vec<f64>> = BTreeMap::new();
+
+        for item in input {
+            let item = item?;
+
+            match interval.bucket(item) {
+                Some(index) => buckets.entry(index).or_default().push(item),
+                None if item < interval.low() => underflow.push(item),
+                // NaN has no place in any bucket, so it lands with the overflow
+                None => overflow.push(item),
+            }
+        }
+
+        let underflow_row = Row::new(Value::Missing)
+            .with("bucket", Value::Missing)
+            .with("low", Value::Missing)
+            .with("high", interval.low());
+        let overflow_row = Row::new(Value::Missing)
+            .with("bucket", Value::Missing)
+            .with("low", interval.high())
+            .with("high", Value::Missing);
+
+        if self.empty || !underflow.is_empty() {
+            let label = format!("< {}", interval.low());
+            write_group(out, &label, underflow_row, &underflow)?;
+        }
+
+        let write_bucket = |out: &mut Output, index: usize, values: &[f64]| {
+            let bucket = interval.bucket_bounds(index);
+            let label = bucket
+                .map(|b| format!("[{}, {})", b.low(), b.high()))
+                .unwrap_or_default();
+
+            write_group(
+                out,
+                &label,
+                Row::new(Value::Missing).with_bucket(bucket),
+                values,
+            )
+        };
+
+        if self.empty {
+            for index in 0..interval.count() as usize {
+                let values = buckets.get(&index).map_or(&[][..], Vec::as_slice);
+                write_bucket(out, index, values)?;
+            }
         } else {
-            let low = interval.low().round() as i64;
-            let high = interval.high().round() as i64;
-            self.range
+            for (&index, values) in buckets.iter() {
+                write_bucket(out, index, values)?;
+            }
+        }
+
+        if self.empty || !overflow.is_empty() {
+            let label = format!(">= {}", interval.high());
+            write_group(out, &label, overflow_row, &overflow)?;
+        }
+
+        Ok(())
+    }
+
+    /// Streams every input item through `selector`, printing its selection
+    /// at the end and whenever `--emit-every` or `--emit-interval` is due
+    fn reduce<T, R>(
+        &self,
+        out: &mut Output,
+        input: Box<dyn Iterator<Item = Result<T, InputError>>>,
+        mut selector: ByBucket<T, R>,
+    ) -> Result<(), color_eyre::eyre::Report>
+    where
+        T: Keyed,
+        R: Reducer<T>,
+        R::Output: Into<Value>,
+    {
+        let emit_interval = self.emit_interval()?;
+        let repeated = self.emit_every.is_some() || emit_interval.is_some();
+        let mut last_emit = Instant::now();
+        let mut emits = 0u64;
+
+        for (seen, item) in (1..).zip(input) {
+            selector.push(item?);
+
+            let count_due = self.emit_every.is_some_and(|every| seen % every.get() == 0);
+            let time_due = emit_interval.is_some_and(|every| last_emit.elapsed() >= every);
+
+            if count_due || time_due {
+                write_selection(out, &selector, repeated.then_some(emits))?;
+                last_emit = Instant::now();
+                emits += 1;
+            }
+        }
+
+        write_selection(out, &selector, repeated.then_some(emits))
+    }
+
+    fn emit_interval(&self) -> Result<Option<Duration>, color_eyre::eyre::Report> {
+        match self.emit_interval {
+            Some(every)
+                if every
+                    .unit()
+                    .is_some_and(|u| u.family() != UnitFamily::Duration) =>
+            {
+                color_eyre::eyre::bail!("`{}` is not a duration", every)
+            }
+            Some(every) if !(every.value().is_finite() && every.value() >= 0.0) => {
+                color_eyre::eyre::bail!(
+                    "Invalid emit interval. Ensure `--emit-interval` is a finite duration >= 0 (was: {})",
+                    every
+                )
+            }
+            Some(every) => Ok(Some(Duration::from_secs_f64(every.value()))),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Writes the current selection as its own group, numbered by `emit` if the
+/// selection is printed more than once
+fn write_selection<T, R>(
+    out: &mut Output,
+    selector: &ByBucket<T, R>,
+    emit: Option<u64>,
+) -> Result<(), color_eyre::eyre::Report>
+where
+    T: Keyed,
+    R: Reducer<T>,
+    R::Output: Into<Value>,
+{
+    out.group(None)?;
+
+    for (bucket, outputs) in selector.results() {
+        for output in outputs {
+            let row = Row::new(output);
+            let row = match emit {
+                Some(emit) => row.with("emit", emit),
+                None => row,
+            };
+
+            out.row(row.with_bucket(Some(bucket)))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes `values` as their own group, each carrying the metadata of `template`
+fn write_group(
+    out: &mut Output,
+    label: &str,
+    template: Row,
+    values: &[f64],
+) -> Result<(), color_eyre::eyre::Report> {
+    out.group(Some(label))?;
+
+    match values.is_empty() {
+        true => out.row(template),
+        false => values
+            .iter()
+            .try_for_each(|&number| out.row(template.clone().with_value(number))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::run_cli;
+
+    #[test]
+    fn emit_cadence() {
+        let values = ["5", "50", "7", "60", "1"];
+        let run = |args: &[&str]| {
+            let args: Vec<&str> = ["range", "--end", "100", "-M"]
+                .iter()
+                .chain(args)
+                .chain(values.iter())
+                .copied()
+                .collect();
+
+            run_cli(&args).unwrap()
+        };
+
+        // Every second value, then once more at the end
+        assert_eq!("5 50\n7 60\n7 60\n", run(&["--emit-every", "2"]));
+        assert_eq!("7 60\n", run(&["--emit-every", "10"]));
+        // A zero interval is always due
+        assert_eq!(6, run(&["--emit-interval", "0s"]).lines().count());
+        assert_eq!(
+            "{\"emit\":0,\"bucket\":0,\"low\":0.0,\"high\":9.049875621121,\"value\":5.0}",
+            run(&["--emit-every", "2", "--format", "jsonl"])
+                .lines()
+                .next()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn group_labels_and_rows() {
+        let group = |args: &[&str]| {
+            let args: Vec<&str> = ["range", "--end", "100", "--count", "3", "--group"]
                 .iter()
-                .filter(|&&item| low <= item && high >= item)
+                .chain(args)
                 .copied()
-                .collect()
+                .collect();
+
+            run_cli(&args).unwrap()
         };
 
-        for &number in &output {
-            write!(dst, "{} ", number)?;
+        // Values below, inside and above the interval, with NaN landing in the overflow
+        assert_eq!(
+            "< 0: -5\n[0, 3.657009507804): 3 2\n[20.687737555775, 100): 50\n>= 100: NaN 150\n",
+            group(&["--", "-5", "3", "50", "2", "nan", "150"])
+        );
+        // Only occupied buckets without `--empty`
+        assert_eq!("[20.687737555775, 100): 50\n", group(&["50"]));
+
+        assert_eq!(
+            "< 0:\n[0, 3.657009507804): 3\n[3.657009507804, 20.687737555775):\n\
+             [20.687737555775, 100): 50\n>= 100:\n",
+            group(&["--empty", "3", "50"])
+        );
+        assert_eq!(
+            "bucket,low,high,value\n,,0,\n0,0,3.657009507804,3\n1,3.657009507804,20.687737555775,\n\
+             2,20.687737555775,100,50\n,100,,\n",
+            group(&["--empty", "--format", "csv", "3", "50"])
+        );
+    }
+
+    #[test]
+    fn emit_err() {
+        for args in &[
+            &["--emit-every", "0"][..],
+            &["--emit-interval", "inf"],
+            &["--emit-interval", "nan"],
+            &["--emit-interval", "1KiB"],
+        ] {
+            let args: Vec<&str> = ["range", "-M"]
+                .iter()
+                .chain(args.iter())
+                .chain(&["1"])
+                .copied()
+                .collect();
+
+            assert!(run_cli(&args).is_err(), "{:?}", args);
         }
-        writeln!(dst)?;
+    }
 
-        Ok(())
+    #[test]
+    fn negative_values() {
+        // Negative values are read as values rather than flags, and fall below `0..=256`
+        assert_eq!("", run_cli(&["range", "-3", "-1"]).unwrap());
+
+        assert_eq!(
+            "-3\n",
+            run_cli(&["range", "--start", "-10", "--end", "10", "-m", "-3", "-1", "2.5", "9"])
+                .unwrap()
+        );
+        assert_eq!(
+            "-3 -0.75\n",
+            run_cli(&[
+                "range", "--start", "-4", "--end", "-0.5", "-M", "-3", "-1", "-3.5", "-0.75"
+            ])
+            .unwrap()
+        );
     }
 }
@@ -0,0 +1,51 @@
+/*
+ * This Source Code Form is subject to the terms of
+ * the Mozilla Public License, v. 2.0. If a copy of
+ * the MPL was not distributed with this file, You
+ * can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use std::str::FromStr;
+
+/// Per-bucket selectors and aggregates available to `range --select`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Select {
+    First,
+    Last,
+    Min,
+    Max,
+    Median,
+    Mean,
+    Sum,
+    Count,
+    NearestLow,
+    NearestHigh,
+    TopK(usize),
+}
+
+impl FromStr for Select {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "first" => Ok(Self::First),
+            "last" => Ok(Self::Last),
+            "min" => Ok(Self::Min),
+            "max" => Ok(Self::Max),
+            "median" => Ok(Self::Median),
+            "mean" => Ok(Self::Mean),
+            "sum" => Ok(Self::Sum),
+            "count" => Ok(Self::Count),
+            "nearest-low" => Ok(Self::NearestLow),
+            "nearest-high" => Ok(Self::NearestHigh),
+            top => match top.strip_prefix("top:").map(str::parse) {
+                Some(Ok(k)) if k > 0 => Ok(Self::TopK(k)),
+                _ => Err(format!(
+                    "Unknown selector. Ensure it is one of `first`, `last`, `min`, `max`, `median`, \
+                     `mean`, `sum`, `count`, `nearest-low`, `nearest-high` or `top:K` (was: {})",
+                    s
+                )),
+            },
+        }
+    }
+}
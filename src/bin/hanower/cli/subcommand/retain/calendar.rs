@@ -0,0 +1,214 @@
+/*
+ * This Source Code Form is subject to the terms of
+ * the Mozilla Public License, v. 2.0. If a copy of
+ * the MPL was not distributed with this file, You
+ * can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use super::{Keep, Snapshot};
+use crate::timestamp::civil_from_days;
+use std::str::FromStr;
+use structopt::StructOpt;
+
+/// Grandfather-father-son retention, which keeps one snapshot from each of the
+/// most recent days, weeks, months and years that have one. Periods are in UTC
+#[derive(Debug, Clone, Default, StructOpt)]
+pub(super) struct Calendar {
+    /// Keep a snapshot from each of the last N days with one
+    #[structopt(long)]
+    daily: Option<usize>,
+
+    /// Keep a snapshot from each of the last N weeks with one
+    #[structopt(long)]
+    weekly: Option<usize>,
+
+    /// Keep a snapshot from each of the last N months with one
+    #[structopt(long)]
+    monthly: Option<usize>,
+
+    /// Keep a snapshot from each of the last N years with one
+    #[structopt(long)]
+    yearly: Option<usize>,
+
+    /// Day weeks start on, for `--weekly`
+    #[structopt(long, default_value = "monday")]
+    week_start: Weekday,
+}
+
+impl Calendar {
+    /// Whether any period has been asked for
+    pub fn is_empty(&self) -> bool {
+        self.rules().iter().all(|(_, n, _)| n.is_none())
+    }
+
+    /// Marks the snapshots this Calendar keeps in `plan`, leaving those already kept alone
+    pub fn apply(&self, keep: Keep, snapshots: &[Snapshot], plan: &mut [Option<&'static str>]) {
+        // Newest first, as periods are counted back from the present
+        let mut order: Vec<&Snapshot> = snapshots.iter().collect();
+        order.sort_by(|a, b| b.time.seconds().total_cmp(&a.time.seconds()));
+
+        for (rule, count, period) in self.rules().iter() {
+            let count = match count {
+                Some(count) => *count,
+                None => continue,
+            };
+
+            let mut periods: Vec<(i64, Vec<&Snapshot>)> = Vec::new();
+            for snapshot in order.iter() {
+                let key = period(self, snapshot);
+
+                let full = periods.len() == count;
+
+                match periods.last_mut() {
+                    Some((current, members)) if *current == key => members.push(snapshot),
+                    _ if full => break,
+                    _ => periods.push((key, vec![snapshot])),
+                }
+            }
+
+            for (_, members) in periods {
+                let chosen = match keep {
+                    Keep::Newest => members.first(),
+                    Keep::Oldest => members.last(),
+                };
+
+                if let Some(snapshot) = chosen {
+                    plan[snapshot.index].get_or_insert(rule);
+                }
+            }
+        }
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn rules(&self) -> [(&'static str, Option<usize>, fn(&Self, &Snapshot) -> i64); 4] {
+        [
+            ("daily", self.daily, |_, s| day(s)),
+            ("weekly", self.weekly, |c, s| {
+                // 1970-01-01 was a Thursday
+                (day(s) + 3 - c.week_start as i64).div_euclid(7)
+            }),
+            ("monthly", self.monthly, |_, s| {
+                let (year, month, _) = civil_from_days(day(s));
+                year * 12 + month as i64
+            }),
+            ("yearly", self.yearly, |_, s| civil_from_days(day(s)).0),
+        ]
+    }
+}
+
+/// Days since 1970-01-01 of a snapshot
+fn day(snapshot: &Snapshot) -> i64 {
+    (snapshot.time.seconds() / 86400.0).floor() as i64
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(super) enum Weekday {
+    #[default]
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl FromStr for Weekday {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "monday" | "mon" => Ok(Self::Monday),
+            "tuesday" | "tue" => Ok(Self::Tuesday),
+            "wednesday" | "wed" => Ok(Self::Wednesday),
+            "thursday" | "thu" => Ok(Self::Thursday),
+            "friday" | "fri" => Ok(Self::Friday),
+            "saturday" | "sat" => Ok(Self::Saturday),
+            "sunday" | "sun" => Ok(Self::Sunday),
+            other => Err(format!("Unknown weekday (was: {})", other)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timestamp::Timestamp;
+
+    fn snapshots(dates: &[&str]) -> Vec<Snapshot> {
+        dates
+            .iter()
+            .enumerate()
+            .map(|(index, date)| Snapshot {
+                id: date.to_string(),
+                time: date.parse::<Timestamp>().unwrap(),
+                age: 0.0,
+                index,
+            })
+            .collect()
+    }
+
+    fn kept(calendar: &Calendar, keep: Keep, snapshots: &[Snapshot]) -> Vec<String> {
+        let mut plan = vec![None; snapshots.len()];
+        calendar.apply(keep, snapshots, &mut plan);
+
+        snapshots
+            .iter()
+            .filter(|s| plan[s.index].is_some())
+            .map(|s| s.id.clone())
+            .collect()
+    }
+
+    #[test]
+    fn daily_and_monthly() {
+        let snapshots = snapshots(&[
+            "2024-03-01T12:00:00Z",
+            "2024-03-01T06:00:00Z",
+            "2024-02-29T12:00:00Z",
+            "2024-02-28T12:00:00Z",
+            "2024-01-31T12:00:00Z",
+            "2024-01-15T12:00:00Z",
+            "2023-12-31T12:00:00Z",
+        ]);
+        let calendar = Calendar {
+            daily: Some(2),
+            monthly: Some(3),
+            ..Calendar::default()
+        };
+
+        assert_eq!(
+            vec![
+                "2024-03-01T12:00:00Z",
+                "2024-02-29T12:00:00Z",
+                "2024-01-31T12:00:00Z"
+            ],
+            kept(&calendar, Keep::Newest, &snapshots)
+        );
+        assert_eq!(
+            vec![
+                "2024-03-01T06:00:00Z",
+                "2024-02-29T12:00:00Z",
+                "2024-02-28T12:00:00Z",
+                "2024-01-15T12:00:00Z"
+            ],
+            kept(&calendar, Keep::Oldest, &snapshots)
+        );
+    }
+
+    #[test]
+    fn week_start() {
+        // A Sunday and the Monday after it
+        let snapshots = snapshots(&["2024-01-08", "2024-01-07"]);
+        let monday = Calendar {
+            weekly: Some(2),
+            ..Calendar::default()
+        };
+        let sunday = Calendar {
+            week_start: Weekday::Sunday,
+            ..monday.clone()
+        };
+
+        assert_eq!(2, kept(&monday, Keep::Newest, &snapshots).len());
+        assert_eq!(1, kept(&sunday, Keep::Newest, &snapshots).len());
+    }
+}
@@ -0,0 +1,358 @@
+/*
+ * This Source Code Form is subject to the terms of
+ * the Mozilla Public License, v. 2.0. If a copy of
+ * the MPL was not distributed with this file, You
+ * can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use super::*;
+use crate::{
+    output::{Format, Output, Row, Value},
+    timestamp::Timestamp,
+};
+use ages::AgeBuckets;
+use args::IntervalArg;
+use calendar::Calendar;
+use hanower::{ByBucket, Interval, Keyed, Reducer, UnitFamily};
+use scan::TimePattern;
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::PathBuf,
+    str::FromStr,
+};
+
+mod calendar;
+mod scan;
+
+/// Interval spec used when neither `--spec` nor a calendar period is given
+const DEFAULT_SPEC: &str = "1d..=365d/12";
+
+/// Plans which snapshots to keep, keeping one per bucket of age and one per
+/// calendar period. Only prints the plan, nothing is ever deleted.
+///
+/// Without calendar periods, snapshots younger than the interval are always kept
+/// and those older are always pruned. With them, one snapshot per bucket of the
+/// interval is kept on top of those the periods keep, while snapshots younger or
+/// older than the interval are only kept by a period
+#[derive(Debug, StructOpt)]
+pub struct Retain {
+    /// Interval spec of snapshot ages, such as `1h..=365d/12`, defaults to `1d..=365d/12`
+    /// unless a calendar period is given. Ages are bucketed in seconds, whatever unit is used
+    #[structopt(long)]
+    spec: Option<IntervalArg>,
+
+    #[structopt(flatten)]
+    calendar: Calendar,
+
+    /// Which snapshot of each bucket or period to keep: `newest` or `oldest`
+    #[structopt(long, default_value = "newest")]
+    keep: Keep,
+
+    /// Time to measure ages from, as seconds since the epoch or an RFC 3339 date,
+    /// defaults to the current time
+    #[structopt(long)]
+    now: Option<Timestamp>,
+
+    /// Read snapshots from this file instead of stdin, one `<id> <timestamp>` per line
+    #[structopt(long, parse(from_os_str))]
+    input: Option<PathBuf>,
+
+    /// Plan for the files in this directory instead, aged by their modification time
+    #[structopt(long, parse(from_os_str), conflicts_with = "input")]
+    dir: Option<PathBuf>,
+
+    /// Only plan for files in `--dir` whose names match, such as `*.tar.zst`, defaults to `*`
+    #[structopt(long, requires = "dir")]
+    glob: Option<String>,
+
+    /// Age files in `--dir` by a timestamp in their name, such as `%Y%m%d-%H%M%S` (UTC)
+    #[structopt(long, requires = "dir")]
+    time_pattern: Option<TimePattern>,
+}
+
+impl Runner for Retain {
+    type Config = Options;
+
+    fn run(
+        &mut self,
+        dst: &mut dyn std::io::Write,
+        config: Option<Self::Config>,
+    ) -> Result<(), color_eyre::eyre::Report> {
+        let now = self.now.unwrap_or_else(Timestamp::now);
+        let entries = match (&self.dir, &self.input) {
+            (Some(dir), _) => {
+                let glob = self.glob.as_deref().unwrap_or("*");
+                scan::scan(dir, glob, self.time_pattern.as_ref())?
+            }
+            (None, Some(path)) => read_entries(Box::new(BufReader::new(File::open(path)?)))?,
+            (None, None) => read_entries(Box::new(BufReader::new(io::stdin())))?,
+        };
+
+        let spec = match (self.spec, self.calendar.is_empty()) {
+            (Some(spec), _) => Some(spec),
+            (None, true) => Some(DEFAULT_SPEC.parse()?),
+            (None, false) => None,
+        };
+        let ages = spec.map(AgeBuckets::new).transpose()?;
+        let snapshots = snapshots(entries, now);
+
+        let mut plan = vec![None; snapshots.len()];
+        self.calendar.apply(self.keep, &snapshots, &mut plan);
+        if let Some(ref ages) = ages {
+            by_age(
+                ages.interval,
+                self.keep,
+                &snapshots,
+                &mut plan,
+                self.calendar.is_empty(),
+            );
+        }
+
+        let format = config
+            .as_ref()
+            .and_then(|c| c.format())
+            .unwrap_or(Format::Words);
+        let numbers = config.as_ref().map(|c| c.numbers()).unwrap_or_default();
+        let mut out = Output::new(dst, format, numbers);
+
+        write_plan(&mut out, ages.as_ref(), &snapshots, &plan)?;
+
+        out.finish()
+    }
+}
+
+/// A snapshot identifier along with its age
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Snapshot {
+    pub id: String,
+    pub time: Timestamp,
+    /// Age in seconds
+    pub age: f64,
+    /// Position in the input, used to tell apart snapshots with the same id
+    pub index: usize,
+}
+
+impl Keyed for Snapshot {
+    fn key(&self) -> f64 {
+        self.age
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Keep {
+    Newest,
+    Oldest,
+}
+
+impl FromStr for Keep {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "newest" => Ok(Self::Newest),
+            "oldest" => Ok(Self::Oldest),
+            other => Err(format!(
+                "Unknown keep policy. Ensure it is one of `newest` or `oldest` (was: {})",
+                other
+            )),
+        }
+    }
+}
+
+/// Reads one `<id> <timestamp>` snapshot per line, skipping empty lines.
+/// The timestamp is the rest of the line, so it may hold spaces, as in `2024-01-09 23:00:00Z`
+fn read_entries(
+    reader: Box<dyn BufRead>,
+) -> Result<Vec<(String, Timestamp)>, color_eyre::eyre::Report> {
+    let mut entries = Vec::new();
+
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+
+        let (id, time) = match line.split_once(char::is_whitespace) {
+            _ if line.is_empty() => continue,
+            Some((id, time)) => (id, time),
+            None => color_eyre::eyre::bail!(
+                "line {}: expected a snapshot id followed by a timestamp",
+                idx + 1
+            ),
+        };
+        let time: Timestamp = time
+            .parse()
+            .map_err(|e| color_eyre::eyre::eyre!("line {}: {}", idx + 1, e))?;
+
+        entries.push((id.to_string(), time));
+    }
+
+    Ok(entries)
+}
+
+/// Ages each `(id, timestamp)` entry relative to `now`
+fn snapshots(entries: Vec<(String, Timestamp)>, now: Timestamp) -> Vec<Snapshot> {
+    entries
+        .into_iter()
+        .enumerate()
+        .map(|(index, (id, time))| {
+            // Snapshots from the future are as young as can be
+            let age = (now.seconds() - time.seconds()).max(0.0);
+
+            Snapshot {
+                id,
+                time,
+                age,
+                index,
+            }
+        })
+        .collect()
+}
+
+/// Marks one snapshot per bucket of age in `plan`, along with every snapshot
+/// younger than the interval if `keep_young` is set
+fn by_age(
+    interval: Interval,
+    keep: Keep,
+    snapshots: &[Snapshot],
+    plan: &mut [Option<&'static str>],
+    keep_young: bool,
+) {
+    let chosen = match keep {
+        Keep::Newest => select(ByBucket::new(interval, hanower::Min), snapshots),
+        Keep::Oldest => select(ByBucket::new(interval, hanower::Max), snapshots),
+    };
+
+    for snapshot in chosen {
+        plan[snapshot.index].get_or_insert("bucket");
+    }
+
+    if keep_young {
+        for snapshot in snapshots.iter().filter(|s| s.age < interval.low()) {
+            plan[snapshot.index].get_or_insert("young");
+        }
+    }
+}
+
+fn select<R>(mut by_bucket: ByBucket<Snapshot, R>, snapshots: &[Snapshot]) -> Vec<Snapshot>
+where
+    R: Reducer<Snapshot, Output = Snapshot>,
+{
+    for snapshot in snapshots {
+        by_bucket.push(snapshot.clone());
+    }
+
+    by_bucket.selection().collect()
+}
+
+/// Writes the `keep` group followed by the `prune` group, each from youngest to oldest,
+/// along with the rule which kept each snapshot
+fn write_plan(
+    out: &mut Output,
+    ages: Option<&AgeBuckets>,
+    snapshots: &[Snapshot],
+    plan: &[Option<&'static str>],
+) -> Result<(), color_eyre::eyre::Report> {
+    let mut order: Vec<&Snapshot> = snapshots.iter().collect();
+    order.sort_by(|a, b| a.age.total_cmp(&b.age));
+
+    for (action, keep) in &[("keep", true), ("prune", false)] {
+        out.group(Some(action))?;
+
+        for snapshot in order.iter().filter(|s| plan[s.index].is_some() == *keep) {
+            let age = Value::Quantity(snapshot.age, UnitFamily::Duration.best_for(snapshot.age));
+            let row = Row::new(Value::Text(snapshot.id.clone()))
+                .with("action", Value::Text(action.to_string()))
+                .with("time", Value::Text(snapshot.time.to_string()))
+                .with("age", age)
+                .with(
+                    "rule",
+                    plan[snapshot.index].map(|rule| Value::Text(rule.into())),
+                );
+
+            out.row(match ages {
+                Some(ages) => ages.with_bucket(row, snapshot.age),
+                None => row,
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plan_keeps_one_per_bucket() {
+        let ages = AgeBuckets::new("1d..=61d/2:linear".parse().unwrap()).unwrap();
+        let now: Timestamp = "2024-01-10".parse().unwrap();
+        let input = "fresh 2024-01-10\na 2024-01-09\nb 2024-01-08\nc 2024-01-01\nd 2023-12-01\nancient 2020-01-01\n";
+
+        let entries = read_entries(Box::new(input.as_bytes())).unwrap();
+        let snapshots = snapshots(entries, now);
+        let kept = |keep| -> Vec<&str> {
+            let mut plan = vec![None; snapshots.len()];
+            by_age(ages.interval, keep, &snapshots, &mut plan, true);
+            snapshots
+                .iter()
+                .filter(|s| plan[s.index].is_some())
+                .map(|s| s.id.as_str())
+                .collect()
+        };
+
+        // Buckets: [1d, 31d) and [31d, 61d)
+        assert_eq!(vec!["fresh", "a", "d"], kept(Keep::Newest));
+        assert_eq!(vec!["fresh", "c", "d"], kept(Keep::Oldest));
+    }
+
+    #[test]
+    fn calendar_and_interval_picks_add_up() {
+        let ages = AgeBuckets::new("1d..=61d/2:linear".parse().unwrap()).unwrap();
+        let calendar = Calendar::from_iter_safe(&["calendar", "--daily", "1"]).unwrap();
+        let now: Timestamp = "2024-01-10T12:00:00Z".parse().unwrap();
+        let input = "fresh 2024-01-10T11:00:00Z\nearly 2024-01-10T01:00:00Z\n\
+                     a 2024-01-09\nb 2024-01-08\nd 2023-12-01\nancient 2020-01-01\n";
+
+        let entries = read_entries(Box::new(input.as_bytes())).unwrap();
+        let snapshots = snapshots(entries, now);
+        let mut plan = vec![None; snapshots.len()];
+        calendar.apply(Keep::Newest, &snapshots, &mut plan);
+        by_age(ages.interval, Keep::Newest, &snapshots, &mut plan, false);
+
+        let kept: Vec<(&str, &str)> = snapshots
+            .iter()
+            .filter_map(|s| plan[s.index].map(|rule| (s.id.as_str(), rule)))
+            .collect();
+
+        // The day's newest snapshot for `--daily`, then the newest of every bucket,
+        // including the first, while young and ancient snapshots have no bucket to keep them
+        assert_eq!(
+            vec![("fresh", "daily"), ("a", "bucket"), ("d", "bucket")],
+            kept
+        );
+    }
+
+    #[test]
+    fn entries_with_spaced_timestamps() {
+        let input = "a 2024-01-09 23:00:00Z\n\n  b\t2024-01-09T23:30:00+00:30  \nc 1704844800\n";
+        let entries = read_entries(Box::new(input.as_bytes())).unwrap();
+        let entries: Vec<(&str, String)> = entries
+            .iter()
+            .map(|(id, time)| (id.as_str(), time.to_string()))
+            .collect();
+
+        assert_eq!(
+            vec![
+                ("a", "2024-01-09T23:00:00Z".to_string()),
+                ("b", "2024-01-09T23:00:00Z".to_string()),
+                ("c", "2024-01-10T00:00:00Z".to_string()),
+            ],
+            entries
+        );
+
+        for bad in &["a\n", "a 2024-01-09 23:00:00Z extra\n"] {
+            assert!(read_entries(Box::new(bad.as_bytes())).is_err(), "{:?}", bad);
+        }
+    }
+}
@@ -0,0 +1,262 @@
+/*
+ * This Source Code Form is subject to the terms of
+ * the Mozilla Public License, v. 2.0. If a copy of
+ * the MPL was not distributed with this file, You
+ * can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use crate::timestamp::{days_from_civil, days_in_month, Timestamp};
+use std::{fs, path::Path, str::FromStr, time::UNIX_EPOCH};
+
+/// Lists the files directly in `dir` whose names match `glob`, along with their
+/// timestamp, taken from the name if there is a `pattern` or the modification time otherwise
+pub(super) fn scan(
+    dir: &Path,
+    glob: &str,
+    pattern: Option<&TimePattern>,
+) -> Result<Vec<(String, Timestamp)>, color_eyre::eyre::Report> {
+    let mut entries = Vec::new();
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+
+        if !entry.file_type()?.is_file() || !glob_match(glob, &name) {
+            continue;
+        }
+
+        let time = match pattern {
+            Some(pattern) => match pattern.find(&name) {
+                Some(time) => time,
+                None => color_eyre::eyre::bail!(
+                    "`{}` does not contain a timestamp matching `{}`",
+                    name,
+                    pattern
+                ),
+            },
+            None => {
+                let modified = entry.metadata()?.modified()?;
+                let seconds = match modified.duration_since(UNIX_EPOCH) {
+                    Ok(since) => since.as_secs_f64(),
+                    Err(before) => -before.duration().as_secs_f64(),
+                };
+
+                Timestamp(seconds)
+            }
+        };
+
+        entries.push((entry.path().to_string_lossy().into_owned(), time));
+    }
+
+    // Directory order is arbitrary, so sort for a stable plan
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+    Ok(entries)
+}
+
+/// Matches `name` against a shell style `glob`, where `*` is any run of
+/// characters and `?` is any single character
+fn glob_match(glob: &str, name: &str) -> bool {
+    let glob: Vec<char> = glob.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut g, mut n) = (0, 0);
+    // Where to resume after the last `*`, as (glob, name) positions
+    let mut star = None;
+
+    while n < name.len() {
+        match glob.get(g) {
+            Some('*') => {
+                star = Some((g + 1, n));
+                g += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                g += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((after, matched)) => {
+                    g = after;
+                    n = matched + 1;
+                    star = Some((after, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    glob[g..].iter().all(|&c| c == '*')
+}
+
+/// A timestamp within a file name, such as `%Y%m%d-%H%M%S`. Supports `%Y`, `%m`,
+/// `%d`, `%H`, `%M` and `%S`, fields left out default to the start of their period.
+/// Times are UTC
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct TimePattern {
+    pattern: String,
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Part {
+    Literal(char),
+    Field(Field),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Year,
+    Month,
+    Day,
+    Hour,
+    Minute,
+    Second,
+}
+
+impl Field {
+    fn width(self) -> usize {
+        match self {
+            Self::Year => 4,
+            _ => 2,
+        }
+    }
+}
+
+impl TimePattern {
+    /// Finds the first timestamp in `name` which matches this pattern
+    fn find(&self, name: &str) -> Option<Timestamp> {
+        let chars: Vec<char> = name.chars().collect();
+
+        (0..chars.len()).find_map(|start| self.match_at(&chars[start..]))
+    }
+
+    fn match_at(&self, chars: &[char]) -> Option<Timestamp> {
+        let (mut year, mut month, mut day) = (1970, 1, 1);
+        let (mut hour, mut minute, mut second) = (0, 0, 0);
+        let mut pos = 0;
+
+        for part in self.parts.iter() {
+            match *part {
+                Part::Literal(c) => {
+                    if chars.get(pos) != Some(&c) {
+                        return None;
+                    }
+                    pos += 1;
+                }
+                Part::Field(field) => {
+                    let digits = chars.get(pos..pos + field.width())?;
+                    if !digits.iter().all(char::is_ascii_digit) {
+                        return None;
+                    }
+                    let value: i64 = digits.iter().collect::<String>().parse().ok()?;
+                    pos += field.width();
+
+                    match field {
+                        Field::Year => year = value,
+                        Field::Month => month = value,
+                        Field::Day => day = value,
+                        Field::Hour => hour = value,
+                        Field::Minute => minute = value,
+                        Field::Second => second = value,
+                    }
+                }
+            }
+        }
+
+        if !(1..=12).contains(&month)
+            || !(1..=days_in_month(year, month as u32) as i64).contains(&day)
+            || hour > 23
+            || minute > 59
+            || second > 60
+        {
+            return None;
+        }
+
+        let days = days_from_civil(year, month as u32, day as u32);
+
+        Some(Timestamp(
+            (days * 86400 + hour * 3600 + minute * 60 + second) as f64,
+        ))
+    }
+}
+
+impl FromStr for TimePattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = Vec::new();
+        let mut chars = s.chars();
+
+        while let Some(c) = chars.next() {
+            let part = match c {
+                '%' => match chars.next() {
+                    Some('Y') => Part::Field(Field::Year),
+                    Some('m') => Part::Field(Field::Month),
+                    Some('d') => Part::Field(Field::Day),
+                    Some('H') => Part::Field(Field::Hour),
+                    Some('M') => Part::Field(Field::Minute),
+                    Some('S') => Part::Field(Field::Second),
+                    Some('%') => Part::Literal('%'),
+                    other => {
+                        return Err(format!(
+                            "Unknown time field `%{}`. Ensure it is one of `%Y`, `%m`, `%d`, `%H`, `%M` or `%S`",
+                            other.map(String::from).unwrap_or_default()
+                        ))
+                    }
+                },
+                c => Part::Literal(c),
+            };
+            parts.push(part);
+        }
+
+        match parts.iter().any(|p| matches!(p, Part::Field(_))) {
+            true => Ok(Self {
+                pattern: s.to_string(),
+                parts,
+            }),
+            false => Err(format!(
+                "Time pattern has no fields, such as `%Y%m%d` (was: {})",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for TimePattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.pattern)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn globs() {
+        let data = vec![
+            ("*.tar.zst", "nightly-2024.tar.zst", true),
+            ("*.tar.zst", "nightly-2024.tar.gz", false),
+            ("nightly-????.*", "nightly-2024.tar.zst", true),
+            ("*", "", true),
+            ("a*b*c", "aXbYbZc", true),
+            ("a*b*c", "aXbYbZ", false),
+        ];
+
+        for (glob, name, expected) in data {
+            assert_eq!(expected, glob_match(glob, name), "{} {}", glob, name);
+        }
+    }
+
+    #[test]
+    fn time_patterns() {
+        let pattern: TimePattern = "%Y%m%d-%H%M".parse().unwrap();
+
+        assert_eq!(
+            Some("2023-11-14T22:13:00Z".parse().unwrap()),
+            pattern.find("db-20231114-2213.tar.zst")
+        );
+        assert_eq!(None, pattern.find("db-20231340-2213.tar.zst"));
+        assert!("backup".parse::<TimePattern>().is_err());
+        assert!("%Q".parse::<TimePattern>().is_err());
+    }
+}
@@ -0,0 +1,88 @@
+/*
+ * This Source Code Form is subject to the terms of
+ * the Mozilla Public License, v. 2.0. If a copy of
+ * the MPL was not distributed with this file, You
+ * can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use super::*;
+use crate::output::{Format, Output, Row};
+use hanower::Rotation;
+
+/// Most runs printed without `--runs`, one full period of a 17 set rotation
+const MAX_DEFAULT_RUNS: u64 = 1 << 16;
+
+/// Prints which backup set to overwrite on each run of a Tower of Hanoi rotation,
+/// with sets and runs numbered from 0
+#[derive(Debug, StructOpt)]
+pub struct Rotate {
+    /// Number of backup sets to rotate through
+    #[structopt(long, default_value = "5")]
+    sets: u32,
+
+    /// First run (or day) of the window to print
+    #[structopt(long, default_value = "0")]
+    start: u64,
+
+    /// Number of runs to print, defaults to one full period of the rotation.
+    /// Required for rotations of more than 17 sets, whose periods are too long to print whole
+    #[structopt(long)]
+    runs: Option<u64>,
+}
+
+impl Runner for Rotate {
+    type Config = Options;
+
+    fn run(
+        &mut self,
+        dst: &mut dyn std::io::Write,
+        config: Option<Self::Config>,
+    ) -> Result<(), color_eyre::eyre::Report> {
+        let rotation = Rotation::new(self.sets)?;
+        let runs = match self.runs {
+            Some(runs) => runs,
+            None if rotation.period() <= MAX_DEFAULT_RUNS => rotation.period(),
+            None => color_eyre::eyre::bail!(
+                "Invalid runs. A full period of {} sets is {} runs, so ensure `--runs` is given",
+                rotation.sets(),
+                rotation.period()
+            ),
+        };
+
+        let format = config
+            .as_ref()
+            .and_then(|c| c.format())
+            .unwrap_or(Format::Words);
+        let numbers = config.as_ref().map(|c| c.numbers()).unwrap_or_default();
+        let mut out = Output::new(dst, format, numbers);
+
+        for (run, set) in rotation.schedule(self.start, runs) {
+            out.row(Row::new(set as u64).with("run", run))?;
+        }
+
+        out.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::run_cli;
+
+    #[test]
+    fn default_runs() {
+        assert_eq!("0 1 0 2\n", run_cli(&["rotate", "--sets", "3"]).unwrap());
+        assert_eq!(
+            1 << 16,
+            run_cli(&["rotate", "--sets", "17"])
+                .unwrap()
+                .split_whitespace()
+                .count()
+        );
+
+        assert!(run_cli(&["rotate", "--sets", "18"]).is_err());
+        assert_eq!(
+            "0 1 0\n",
+            run_cli(&["rotate", "--sets", "64", "--runs", "3"]).unwrap()
+        );
+    }
+}
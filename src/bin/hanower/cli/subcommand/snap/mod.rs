@@ -0,0 +1,70 @@
+/*
+ * This Source Code Form is subject to the terms of
+ * the Mozilla Public License, v. 2.0. If a copy of
+ * the MPL was not distributed with this file, You
+ * can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use super::*;
+use crate::{
+    input::InputArgs,
+    output::{Format, Output, Row},
+};
+use args::IntervalArg;
+use hanower::Rounding;
+use structopt::clap::AppSettings::AllowNegativeNumbers;
+
+/// Picks the allowed values closest to each fence of an interval
+#[derive(Debug, StructOpt)]
+#[structopt(setting = AllowNegativeNumbers)]
+pub struct Snap {
+    /// Interval spec whose fences are snapped, such as `1..=1024/10`
+    #[structopt(long, allow_hyphen_values = true)]
+    spec: IntervalArg,
+
+    /// Which candidates a fence may snap to: `floor`, `ceil` or `nearest`
+    #[structopt(long, default_value = "nearest")]
+    rounding: Rounding,
+
+    /// Use each candidate at most once
+    #[structopt(long)]
+    unique: bool,
+
+    #[structopt(flatten)]
+    input: InputArgs,
+
+    /// Allowed values, such as `2 4 8 16`, read from stdin or `--input` if empty
+    #[structopt(allow_hyphen_values = true, conflicts_with = "input")]
+    candidates: Vec<f64>,
+}
+
+impl Runner for Snap {
+    type Config = Options;
+
+    fn run(
+        &mut self,
+        dst: &mut dyn std::io::Write,
+        config: Option<Self::Config>,
+    ) -> Result<(), color_eyre::eyre::Report> {
+        let candidates = match self.candidates.is_empty() {
+            true => self.input.values()?.collect::<Result<Vec<_>, _>>()?,
+            false => self.candidates.clone(),
+        };
+
+        let format = config
+            .as_ref()
+            .and_then(|c| c.format())
+            .unwrap_or(Format::Words);
+        let numbers = config.as_ref().map(|c| c.numbers()).unwrap_or_default();
+        let mut out = Output::new(dst, format, numbers);
+        let interval = self.spec.interval;
+
+        // Fences without a matching candidate are only listed by structured formats
+        let snapped = interval.snap(&candidates, self.rounding, self.unique);
+        for ((index, fence), number) in (0u64..).zip(interval.intervals()).zip(snapped) {
+            out.row(Row::new(number).with("index", index).with("fence", fence))?;
+        }
+
+        out.finish()
+    }
+}
@@ -0,0 +1,369 @@
+/*
+ * This Source Code Form is subject to the terms of
+ * the Mozilla Public License, v. 2.0. If a copy of
+ * the MPL was not distributed with this file, You
+ * can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use super::*;
+use crate::output::{Format, Output, Row, Value};
+use args::{integral, IntervalArg};
+use hanower::{Interval, Quantity, Rounding, Spacing, Unit, UnitFamily};
+use std::{
+    collections::BTreeMap,
+    io,
+    process::{Command, Stdio},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use structopt::clap::AppSettings::AllowNegativeNumbers;
+
+/// Runs a command once per fence of an interval, such as
+/// `sweep --count 12 1 4096 -- ./bench --threads {}`, printing the stdout,
+/// exit status and duration of each run.
+///
+/// Fences replace every `{}` in the command, or are appended to it if there is none.
+/// Fences are given in base units, such as bytes or seconds, whatever the number format.
+/// Plain numbers and byte sizes which are whole, such as `1` or `4KiB`, sweep distinct
+/// integer fences
+#[derive(Debug, StructOpt)]
+#[structopt(setting = AllowNegativeNumbers)]
+pub struct Sweep {
+    /// Interval spec, such as `1..=4096/12`, used instead of `low`, `high` and `--count`
+    #[structopt(long, allow_hyphen_values = true, conflicts_with_all = &["low", "high", "count"])]
+    spec: Option<IntervalArg>,
+
+    /// Number of intervals
+    #[structopt(long, default_value = "2")]
+    count: u64,
+
+    /// Number of commands to run at once. Results are printed in fence order regardless
+    #[structopt(long, default_value = "1")]
+    jobs: usize,
+
+    /// Text in the command to replace with each fence
+    #[structopt(long, default_value = "{}")]
+    placeholder: String,
+
+    /// Start point of the sweep, such as `1` or `4KiB`
+    #[structopt(allow_hyphen_values = true, required_unless = "spec")]
+    low: Option<Quantity>,
+
+    /// End point of the sweep, such as `4096` or `1GiB`
+    #[structopt(allow_hyphen_values = true, required_unless = "spec")]
+    high: Option<Quantity>,
+
+    /// Command to run, along with its arguments, given after `--`
+    #[structopt(last = true, required = true)]
+    command: Vec<String>,
+}
+
+impl Sweep {
+    /// Returns the Interval to sweep, along with the unit it was given in
+    fn interval(&self) -> Result<(Interval, Option<Unit>), color_eyre::eyre::Report> {
+        let (interval, unit) = match (self.spec, self.low, self.high) {
+            (Some(spec), _, _) => (spec.interval, spec.unit),
+            (None, Some(low), Some(high)) => {
+                Interval::from_quantities(low, high, self.count, Spacing::default())?
+            }
+            _ => unreachable!("clap requires `low` and `high` unless `--spec` is given"),
+        };
+
+        Ok((interval, unit))
+    }
+}
+
+impl Runner for Sweep {
+    type Config = Options;
+
+    fn run(
+        &mut self,
+        dst: &mut dyn std::io::Write,
+        config: Option<Self::Config>,
+    ) -> Result<(), color_eyre::eyre::Report> {
+        if self.jobs == 0 {
+            color_eyre::eyre::bail!("Invalid jobs. Ensure `--jobs` is >= 1 (was: 0)")
+        }
+
+        let (interval, unit) = self.interval()?;
+        let integral = integral(&interval, unit);
+        let fences: Vec<f64> = match integral {
+            true => interval
+                .integers(Rounding::Nearest)
+                .into_iter()
+                .map(|fence| fence as f64)
+                .collect(),
+            false => interval.intervals().collect(),
+        };
+
+        // Results are tables, which read best with a header
+        let format = config
+            .as_ref()
+            .and_then(|c| c.format())
+            .unwrap_or(Format::Csv);
+        let numbers = config.as_ref().map(|c| c.numbers()).unwrap_or_default();
+        let numbers = match integral && unit.is_none() {
+            true => numbers.or_decimals(Some(0)),
+            false => numbers,
+        };
+        let mut out = Output::new(dst, format, numbers);
+
+        let commands: Vec<Vec<String>> = fences
+            .iter()
+            .map(|fence| substitute(&self.command, &self.placeholder, &fence.to_string()))
+            .collect();
+
+        let fence = |fence: f64| match unit {
+            Some(unit) => Value::Quantity(fence, unit.family().best_for(fence)),
+            None => Value::Number(fence),
+        };
+
+        run_all(&commands, self.jobs, |index, result| {
+            let result = result.map_err(|e| {
+                color_eyre::eyre::eyre!("failed to run `{}`: {}", commands[index].join(" "), e)
+            })?;
+            let seconds = result.duration.as_secs_f64();
+
+            out.row(
+                Row::new(Value::Text(result.stdout))
+                    .with("index", index as u64)
+                    .with("fence", fence(fences[index]))
+                    .with("status", result.status.map(i64::from))
+                    .with(
+                        "duration",
+                        Value::Quantity(seconds, UnitFamily::Duration.best_for(seconds)),
+                    ),
+            )
+        })?;
+
+        out.finish()
+    }
+}
+
+/// What one run of a command left behind
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Outcome {
+    /// Exit code, or `None` if the command was killed by a signal
+    pub status: Option<i32>,
+    /// Stdout, without its trailing newline
+    pub stdout: String,
+    pub duration: Duration,
+}
+
+/// Replaces `placeholder` in every argument of `command` with `value`,
+/// or appends `value` if no argument holds `placeholder`
+pub(super) fn substitute(command: &[String], placeholder: &str, value: &str) -> Vec<String> {
+    match command.iter().any(|arg| arg.contains(placeholder)) {
+        true => command
+            .iter()
+            .map(|arg| arg.replace(placeholder, value))
+            .collect(),
+        false => command
+            .iter()
+            .cloned()
+            .chain(std::iter::once(value.to_string()))
+            .collect(),
+    }
+}
+
+/// Runs a single command, with stderr passed through
+pub(super) fn execute(command: &[String]) -> io::Result<Outcome> {
+    let (program, args) = command
+        .split_first()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty command"))?;
+
+    let start = Instant::now();
+    let output = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::inherit())
+        .output()?;
+    let duration = start.elapsed();
+
+    let mut stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    let trimmed = stdout.trim_end_matches(['\n', '\r']).len();
+    stdout.truncate(trimmed);
+
+    Ok(Outcome {
+        status: output.status.code(),
+        stdout,
+        duration,
+    })
+}
+
+/// Runs every command, at most `jobs` at a time, passing each result to `report`
+/// in the order of `commands`. Stops starting new commands once `report` fails
+fn run_all<F>(
+    commands: &[Vec<String>],
+    jobs: usize,
+    mut report: F,
+) -> Result<(), color_eyre::eyre::Report>
+where
+    F: FnMut(usize, io::Result<Outcome>) -> Result<(), color_eyre::eyre::Report>,
+{
+    let next = AtomicUsize::new(0);
+    let (tx, rx) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.min(commands.len()) {
+            let tx = tx.clone();
+            let next = &next;
+
+            scope.spawn(move || loop {
+                let index = next.fetch_add(1, Ordering::SeqCst);
+                let command = match commands.get(index) {
+                    Some(command) => command,
+                    None => break,
+                };
+
+                // The receiver is only gone once reporting has failed
+                if tx.send((index, execute(command))).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(tx);
+
+        // Results arrive in the order they finish, so hold on to them until it is their turn
+        let mut finished = BTreeMap::new();
+        let mut due = 0;
+        for (index, result) in rx {
+            finished.insert(index, result);
+
+            while let Some(result) = finished.remove(&due) {
+                report(due, result)?;
+                due += 1;
+            }
+        }
+
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(unix)]
+    use crate::cli::run_cli;
+
+    fn command(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn substitutes_fences() {
+        assert_eq!(
+            command(&["bench", "--threads", "8", "--label=t8"]),
+            substitute(
+                &command(&["bench", "--threads", "{}", "--label=t{}"]),
+                "{}",
+                "8"
+            )
+        );
+        assert_eq!(
+            command(&["echo", "8"]),
+            substitute(&command(&["echo"]), "{}", "8")
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn reports_in_order() {
+        let commands: Vec<Vec<String>> = (0..8)
+            .map(|n| {
+                command(&[
+                    "sh",
+                    "-c",
+                    &format!("sleep 0.0{}; echo {}; exit {}", 8 - n, n, n % 2),
+                ])
+            })
+            .collect();
+        let mut seen = Vec::new();
+
+        run_all(&commands, 4, |index, result| {
+            let result = result.unwrap();
+            seen.push((index, result.stdout, result.status));
+            Ok(())
+        })
+        .unwrap();
+
+        let expected: Vec<_> = (0..8)
+            .map(|n| (n, n.to_string(), Some(n as i32 % 2)))
+            .collect();
+        assert_eq!(expected, seen);
+        assert!(execute(&command(&["hanower-no-such-command"])).is_err());
+    }
+
+    /// Runs `sweep` on a command echoing its arguments, returning the index, status
+    /// and arguments of each run
+    #[cfg(unix)]
+    fn echoed(args: &[&str]) -> Vec<(String, String, String)> {
+        let args: Vec<&str> = ["sweep"]
+            .iter()
+            .chain(args)
+            .chain(&["--", "sh", "-c", "echo \"$*\"; exit 3", "sh", "{}"])
+            .copied()
+            .collect();
+
+        run_cli(&args)
+            .unwrap()
+            .lines()
+            .skip(1)
+            .map(|line| {
+                let (index, rest) = line.split_once(',').unwrap();
+                let mut fields = rest.rsplitn(4, ',');
+                let argv = fields.next().unwrap().to_string();
+                let status = fields.nth(1).unwrap().to_string();
+                (index.to_string(), status, argv)
+            })
+            .collect()
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn passes_raw_fences() {
+        let runs = |expected: &[&str]| -> Vec<(String, String, String)> {
+            expected
+                .iter()
+                .enumerate()
+                .map(|(index, argv)| (index.to_string(), "3".to_string(), argv.to_string()))
+                .collect()
+        };
+
+        // The number format is only for the table
+        assert_eq!(
+            runs(&["1000", "1013", "1207", "4000"]),
+            echoed(&[
+                "--thousands",
+                ",",
+                "--decimals",
+                "2",
+                "--width",
+                "12",
+                "--count",
+                "3",
+                "1000",
+                "4000"
+            ])
+        );
+        assert_eq!(
+            runs(&["1024", "1078", "4096"]),
+            echoed(&["--spec", "1KiB..=4KiB/2", "--notation", "scientific"])
+        );
+
+        // Durations are never rounded to whole seconds
+        let seconds = echoed(&["--spec", "1s..=5s/8"]);
+        assert_eq!(9, seconds.len());
+        assert_eq!(("1", "5"), (&*seconds[0].2, &*seconds[8].2));
+        assert_eq!("2.2360679775", seconds[4].2);
+        assert!(integral(&Interval::new(1.0, 5.0, 8).unwrap(), None));
+        assert!(!integral(
+            &Interval::new(1.0, 5.0, 8).unwrap(),
+            Some("s".parse::<Unit>().unwrap())
+        ));
+    }
+}
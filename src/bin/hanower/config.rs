@@ -5,8 +5,29 @@
  * can obtain one at http://mozilla.org/MPL/2.0/.
  */
 
+use crate::{notation::NumberFormat, output::Format};
+
 #[derive(Debug)]
 #[non_exhaustive]
 pub enum Options {
-    V1,
+    V1 {
+        format: Option<Format>,
+        numbers: NumberFormat,
+    },
+}
+
+impl Options {
+    /// The output format picked on the command line, if any
+    pub fn format(&self) -> Option<Format> {
+        match self {
+            Self::V1 { format, .. } => *format,
+        }
+    }
+
+    /// How numbers are printed, as picked on the command line
+    pub fn numbers(&self) -> NumberFormat {
+        match self {
+            Self::V1 { numbers, .. } => *numbers,
+        }
+    }
 }
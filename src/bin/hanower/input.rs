@@ -0,0 +1,500 @@
+/*
+ * This Source Code Form is subject to the terms of
+ * the Mozilla Public License, v. 2.0. If a copy of
+ * the MPL was not distributed with this file, You
+ * can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use hanower::Keyed;
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::PathBuf,
+    str::FromStr,
+};
+use structopt::StructOpt;
+
+/// Lazily evaluated values read from an input source
+pub type Values = Box<dyn Iterator<Item = Result<f64, InputError>>>;
+
+/// Lazily evaluated records read from an input source
+pub type Records = Box<dyn Iterator<Item = Result<Record, InputError>>>;
+
+/// A whole line of input, along with the value of its key field
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    pub key: f64,
+    pub line: String,
+}
+
+impl Keyed for Record {
+    fn key(&self) -> f64 {
+        self.key
+    }
+}
+
+/// Arguments shared by every subcommand which reads values from stdin or a file
+#[derive(Debug, StructOpt)]
+pub struct InputArgs {
+    /// Read values from this file instead of stdin
+    #[structopt(long, parse(from_os_str))]
+    input: Option<PathBuf>,
+
+    /// How values are separated: `lines` (any whitespace), `nul`, `csv`, `tsv` or `jsonl`
+    #[structopt(long, default_value = "lines")]
+    input_format: InputFormat,
+
+    /// Column holding the values of csv or tsv input, as a header name or 1-based index.
+    /// With `--records`, the whitespace separated field of lines or nul input
+    #[structopt(long, conflicts_with = "json-pointer")]
+    column: Option<Column>,
+
+    /// JSON pointer to the value of each jsonl line, such as `/latency/p99`
+    #[structopt(long)]
+    json_pointer: Option<String>,
+
+    /// Treat the first row of input as a header, implied when `--column` is a name
+    #[structopt(long)]
+    header: bool,
+}
+
+impl InputArgs {
+    /// Opens the input source, returning an iterator over every value in it
+    pub fn values(&self) -> Result<Values, InputError> {
+        self.values_from(self.open()?)
+    }
+
+    /// Opens the input source, returning an iterator over every line in it,
+    /// keyed by the `--column` or `--json-pointer` field
+    pub fn records(&self) -> Result<Records, InputError> {
+        self.records_from(self.open()?)
+    }
+
+    fn open(&self) -> Result<Box<dyn BufRead>, InputError> {
+        match self.input {
+            Some(ref path) => Ok(Box::new(BufReader::new(File::open(path)?))),
+            None => Ok(Box::new(BufReader::new(io::stdin()))),
+        }
+    }
+
+    fn records_from(&self, reader: Box<dyn BufRead>) -> Result<Records, InputError> {
+        let lines: Box<dyn Iterator<Item = io::Result<String>>> = match self.input_format {
+            InputFormat::Nul => Box::new(
+                reader
+                    .split(b'\0')
+                    .map(|record| record.map(|r| String::from_utf8_lossy(&r).into_owned())),
+            ),
+            _ => Box::new(reader.lines()),
+        };
+        let mut lines = lines
+            .enumerate()
+            .map(|(idx, line)| (idx as u64 + 1, line))
+            .filter(|(_, line)| line.as_ref().map_or(true, |l| !l.trim().is_empty()));
+
+        let field = match self.input_format {
+            InputFormat::Jsonl => KeyField::Json(self.json_pointer.clone().unwrap_or_default()),
+            format => {
+                let delimiter = match format {
+                    InputFormat::Csv => Some(b','),
+                    InputFormat::Tsv => Some(b'\t'),
+                    _ => None,
+                };
+
+                let index = match self.column {
+                    None => 0,
+                    Some(Column::Index(index)) => index,
+                    Some(Column::Name(ref name)) => {
+                        let header = match lines.next() {
+                            Some((_, line)) => line?,
+                            None => String::new(),
+                        };
+
+                        split_fields(&header, delimiter)
+                            .iter()
+                            .position(|field| field.trim() == name)
+                            .ok_or_else(|| InputError::UnknownColumn(name.clone()))?
+                    }
+                };
+
+                if self.header && !matches!(self.column, Some(Column::Name(_))) {
+                    if let Some((_, Err(e))) = lines.next() {
+                        return Err(e.into());
+                    }
+                }
+
+                KeyField::Column { delimiter, index }
+            }
+        };
+
+        Ok(Box::new(lines.map(move |(line_no, line)| {
+            let line = line?;
+            let key = field.key(&line, line_no)?;
+
+            Ok(Record { key, line })
+        })))
+    }
+
+    fn values_from(&self, reader: Box<dyn BufRead>) -> Result<Values, InputError> {
+        match self.input_format {
+            InputFormat::Lines => Ok(Box::new(reader.lines().enumerate().flat_map(
+                |(idx, line)| {
+                    match line {
+                        Ok(line) => tokens(&line)
+                            .map(|(column, token)| parse_value(token, idx as u64 + 1, column))
+                            .collect::<Vec<_>>(),
+                        Err(e) => vec![Err(e.into())],
+                    }
+                },
+            ))),
+            InputFormat::Nul => Ok(Box::new(reader.split(b'\0').enumerate().filter_map(
+                |(idx, record)| match record {
+                    Ok(record) => {
+                        let record = String::from_utf8_lossy(&record);
+
+                        match record.trim() {
+                            "" => None,
+                            token => Some(parse_value(token, idx as u64 + 1, 1)),
+                        }
+                    }
+                    Err(e) => Some(Err(e.into())),
+                },
+            ))),
+            InputFormat::Csv => self.delimited(reader, b','),
+            InputFormat::Tsv => self.delimited(reader, b'\t'),
+            InputFormat::Jsonl => Ok(Box::new(
+                self.records_from(reader)?
+                    .map(|record| record.map(|record| record.key)),
+            )),
+        }
+    }
+
+    fn delimited(&self, reader: Box<dyn BufRead>, delimiter: u8) -> Result<Values, InputError> {
+        let header = self.header || matches!(self.column, Some(Column::Name(_)));
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .has_headers(header)
+            .flexible(true)
+            .from_reader(reader);
+
+        let index = match self.column {
+            None => 0,
+            Some(Column::Index(index)) => index,
+            Some(Column::Name(ref name)) => reader
+                .headers()?
+                .iter()
+                .position(|field| field.trim() == name)
+                .ok_or_else(|| InputError::UnknownColumn(name.clone()))?,
+        };
+
+        Ok(Box::new(reader.into_records().map(move |record| {
+            let record = record?;
+            let line = record.position().map(|pos| pos.line()).unwrap_or(0);
+
+            record
+                .get(index)
+                .ok_or(InputError::MissingColumn {
+                    line,
+                    column: index + 1,
+                })
+                .and_then(|field| parse_value(field, line, index + 1))
+        })))
+    }
+}
+
+/// Where the key of a record is found
+#[derive(Debug)]
+enum KeyField {
+    /// A 0-based field, split on `delimiter` or on whitespace if there is none
+    Column { delimiter: Option<u8>, index: usize },
+    /// A JSON pointer into a jsonl line
+    Json(String),
+}
+
+impl KeyField {
+    fn key(&self, line: &str, line_no: u64) -> Result<f64, InputError> {
+        match self {
+            Self::Column { delimiter, index } => split_fields(line, *delimiter)
+                .get(*index)
+                .ok_or(InputError::MissingColumn {
+                    line: line_no,
+                    column: index + 1,
+                })
+                .and_then(|field| parse_value(field, line_no, index + 1)),
+            Self::Json(pointer) => {
+                let json: serde_json::Value =
+                    serde_json::from_str(line).map_err(|error| InputError::InvalidJson {
+                        line: line_no,
+                        error,
+                    })?;
+
+                match json.pointer(pointer) {
+                    Some(serde_json::Value::Number(number)) => {
+                        number.as_f64().ok_or_else(|| InputError::InvalidNumber {
+                            line: line_no,
+                            column: 1,
+                            value: number.to_string(),
+                        })
+                    }
+                    Some(serde_json::Value::String(value)) => parse_value(value, line_no, 1),
+                    Some(other) => Err(InputError::InvalidNumber {
+                        line: line_no,
+                        column: 1,
+                        value: other.to_string(),
+                    }),
+                    None => Err(InputError::MissingPointer {
+                        line: line_no,
+                        pointer: pointer.clone(),
+                    }),
+                }
+            }
+        }
+    }
+}
+
+/// Splits a single line into fields, as csv if there is a `delimiter` or on whitespace otherwise
+fn split_fields(line: &str, delimiter: Option<u8>) -> Vec<String> {
+    match delimiter {
+        Some(delimiter) => csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .has_headers(false)
+            .from_reader(line.as_bytes())
+            .records()
+            .next()
+            .and_then(Result::ok)
+            .map(|record| record.iter().map(str::to_string).collect())
+            .unwrap_or_default(),
+        None => line.split_whitespace().map(str::to_string).collect(),
+    }
+}
+
+/// Splits a line on whitespace, returning each token with its 1-based column
+fn tokens(line: &str) -> impl Iterator<Item = (usize, &str)> {
+    line.split_whitespace().map(move |token| {
+        let offset = token.as_ptr() as usize - line.as_ptr() as usize;
+
+        (line[..offset].chars().count() + 1, token)
+    })
+}
+
+fn parse_value(token: &str, line: u64, column: usize) -> Result<f64, InputError> {
+    token
+        .trim()
+        .parse::<f64>()
+        .map_err(|_| InputError::InvalidNumber {
+            line,
+            column,
+            value: token.to_string(),
+        })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum InputFormat {
+    Lines,
+    Nul,
+    Csv,
+    Tsv,
+    Jsonl,
+}
+
+impl FromStr for InputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lines" => Ok(Self::Lines),
+            "nul" => Ok(Self::Nul),
+            "csv" => Ok(Self::Csv),
+            "tsv" => Ok(Self::Tsv),
+            "jsonl" => Ok(Self::Jsonl),
+            other => Err(format!(
+                "Unknown input format. Ensure it is one of `lines`, `nul`, `csv`, `tsv` or `jsonl` (was: {})",
+                other
+            )),
+        }
+    }
+}
+
+/// A csv or tsv column, stored as a 0-based index
+#[derive(Debug, Clone, PartialEq)]
+enum Column {
+    Index(usize),
+    Name(String),
+}
+
+impl FromStr for Column {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse::<usize>() {
+            Ok(0) => Err("Invalid column. Column indexes start at 1".to_string()),
+            Ok(index) => Ok(Self::Index(index - 1)),
+            Err(_) => Ok(Self::Name(s.to_string())),
+        }
+    }
+}
+
+/// Error kinds for reading input values
+#[derive(Debug)]
+pub enum InputError {
+    /// Occurs when the input source cannot be read
+    Io(io::Error),
+    /// Occurs when csv or tsv input is malformed
+    Csv(csv::Error),
+    /// Occurs when `--column` names a header which does not exist
+    UnknownColumn(String),
+    /// Occurs when a row is too short to contain `--column`
+    MissingColumn { line: u64, column: usize },
+    /// Occurs when a value cannot be parsed as a number
+    InvalidNumber {
+        line: u64,
+        column: usize,
+        value: String,
+    },
+    /// Occurs when a jsonl line is not valid JSON
+    InvalidJson { line: u64, error: serde_json::Error },
+    /// Occurs when `--json-pointer` does not exist in a jsonl line
+    MissingPointer { line: u64, pointer: String },
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "Unable to read input: {}", e),
+            Self::Csv(e) => write!(f, "Unable to read input: {}", e),
+            Self::UnknownColumn(name) => write!(f, "No column named `{}` in the header", name),
+            Self::MissingColumn { line, column } => {
+                write!(f, "line {}, column {}: missing value", line, column)
+            }
+            Self::InvalidNumber {
+                line,
+                column,
+                value,
+            } => write!(
+                f,
+                "line {}, column {}: unable to parse as a number: {}",
+                line, column, value
+            ),
+            Self::InvalidJson { line, error } => {
+                write!(f, "line {}: invalid JSON: {}", line, error)
+            }
+            Self::MissingPointer { line, pointer } => {
+                write!(f, "line {}: no value at `{}`", line, pointer)
+            }
+        }
+    }
+}
+
+impl std::error::Error for InputError {}
+
+impl From<io::Error> for InputError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<csv::Error> for InputError {
+    fn from(e: csv::Error) -> Self {
+        Self::Csv(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(args: &[&str], input: &'static str) -> Vec<Result<f64, InputError>> {
+        let args = InputArgs::from_iter_safe(std::iter::once("input").chain(args.iter().copied()))
+            .unwrap();
+
+        args.values_from(Box::new(input.as_bytes()))
+            .unwrap()
+            .collect()
+    }
+
+    fn read_ok(args: &[&str], input: &'static str) -> Vec<f64> {
+        read(args, input).into_iter().map(Result::unwrap).collect()
+    }
+
+    #[test]
+    fn read_lines() {
+        assert_eq!(
+            vec![1.0, -2.5, 1e6, 4.0],
+            read_ok(&[], "1 -2.5\n\n  1e6\t4\n")
+        );
+    }
+
+    #[test]
+    fn read_nul() {
+        assert_eq!(
+            vec![1.0, 2.0, 3.5],
+            read_ok(&["--input-format", "nul"], "1\x002\x003.5\x00")
+        );
+    }
+
+    #[test]
+    fn read_csv_columns() {
+        let csv = "name,size\na,1\nb,2.5\n";
+        let tsv = "a\t1\nb\t2.5\n";
+
+        assert_eq!(
+            vec![1.0, 2.5],
+            read_ok(&["--input-format", "csv", "--column", "size"], csv)
+        );
+        assert_eq!(
+            vec![1.0, 2.5],
+            read_ok(&["--input-format", "tsv", "--column", "2"], tsv)
+        );
+    }
+
+    #[test]
+    fn read_error_position() {
+        match read(&[], "1 2\n3 oops 4\n").remove(3) {
+            Err(InputError::InvalidNumber { line, column, .. }) => {
+                assert_eq!((2, 3), (line, column))
+            }
+            other => panic!("Expected InvalidNumber, received {:?}", other),
+        }
+
+        match read(&["--input-format", "csv", "--column", "2"], "1,2\n3,x\n").remove(1) {
+            Err(InputError::InvalidNumber { line, column, .. }) => {
+                assert_eq!((2, 2), (line, column))
+            }
+            other => panic!("Expected InvalidNumber, received {:?}", other),
+        }
+    }
+
+    #[test]
+    fn read_records() {
+        let records = |args: &[&str], input: &'static str| -> Vec<(f64, String)> {
+            InputArgs::from_iter_safe(std::iter::once("input").chain(args.iter().copied()))
+                .unwrap()
+                .records_from(Box::new(input.as_bytes()))
+                .unwrap()
+                .map(|r| r.map(|r| (r.key, r.line)).unwrap())
+                .collect()
+        };
+
+        assert_eq!(
+            vec![(40.0, "b\t40\tx y".to_string())],
+            records(
+                &["--input-format", "tsv", "--column", "size"],
+                "name\tsize\tnote\nb\t40\tx y\n"
+            )
+        );
+        assert_eq!(
+            vec![(7.0, "c 7".to_string())],
+            records(&["--column", "2", "--header"], "name n\nc 7\n")
+        );
+        assert_eq!(
+            vec![
+                (3.0, r#"{"a":{"b":3}}"#.to_string()),
+                (5.0, r#"{"a":{"b":"5"}}"#.to_string())
+            ],
+            records(
+                &["--input-format", "jsonl", "--json-pointer", "/a/b"],
+                "{\"a\":{\"b\":3}}\n{\"a\":{\"b\":\"5\"}}\n"
+            )
+        );
+    }
+}
@@ -7,7 +7,11 @@
 
 mod cli;
 mod config;
+mod input;
+mod notation;
+mod output;
 mod run;
+mod timestamp;
 
 use cli::Root;
 use color_eyre::eyre::Result;
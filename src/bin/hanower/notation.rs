@@ -0,0 +1,178 @@
+/*
+ * This Source Code Form is subject to the terms of
+ * the Mozilla Public License, v. 2.0. If a copy of
+ * the MPL was not distributed with this file, You
+ * can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use hanower::SIGNIFICANT;
+use std::str::FromStr;
+use structopt::StructOpt;
+
+// How every subcommand prints numbers, kept out of doc comments since
+// flattening it would replace the about text of the root command
+#[derive(Debug, Clone, Copy, Default, StructOpt)]
+pub struct NumberFormat {
+    /// Number notation: `plain`, `scientific` (1.5e4) or `engineering` (15e3)
+    #[structopt(long, global = true, default_value = "plain")]
+    notation: Notation,
+
+    /// Print exactly this many decimal places
+    #[structopt(long, global = true)]
+    decimals: Option<usize>,
+
+    /// Separate thousands with this character, such as `,` or `_`
+    #[structopt(long, global = true)]
+    thousands: Option<char>,
+
+    /// Pad every number to at least this width, aligned right
+    #[structopt(long, global = true)]
+    width: Option<usize>,
+}
+
+impl NumberFormat {
+    /// Uses `decimals` unless a number of decimal places was picked on the command line
+    pub fn or_decimals(self, decimals: Option<usize>) -> Self {
+        Self {
+            decimals: self.decimals.or(decimals),
+            ..self
+        }
+    }
+
+    /// Writes `number` followed by `suffix`, such as a unit
+    pub fn format(&self, number: f64, suffix: &str) -> String {
+        let text = match number.is_finite() {
+            true => match self.notation {
+                Notation::Plain => self.plain(number),
+                Notation::Scientific => self.exponent(number, 1),
+                Notation::Engineering => self.exponent(number, 3),
+            },
+            false => number.to_string(),
+        };
+
+        format!("{:>width$}", text + suffix, width = self.width.unwrap_or(0))
+    }
+
+    fn plain(&self, number: f64) -> String {
+        let text = match self.decimals {
+            Some(decimals) => format!("{:.*}", decimals, number),
+            None => number.to_string(),
+        };
+
+        match self.thousands {
+            Some(separator) => group_thousands(&text, separator),
+            None => text,
+        }
+    }
+
+    /// Writes `number` as a mantissa and an exponent which is a multiple of `step`
+    fn exponent(&self, number: f64, step: i32) -> String {
+        if number == 0.0 {
+            return self.plain(0.0) + "e0";
+        }
+
+        let exponent = (number.abs().log10().floor() as i32).div_euclid(step) * step;
+        let mantissa = number / 10f64.powi(exponent);
+        // Dividing by a power of ten leaves noise in the last few bits
+        let mantissa = match self.decimals {
+            Some(_) => mantissa,
+            None => (mantissa * SIGNIFICANT).round() / SIGNIFICANT,
+        };
+
+        // Rounding may carry the mantissa over to the next exponent, e.g. 9.99 to 10.0
+        let text = self.plain(mantissa);
+        let limit = 10f64.powi(step);
+        let digits: String = text
+            .chars()
+            .filter(|c| c.is_ascii_digit() || *c == '.')
+            .collect();
+        match digits.parse::<f64>() {
+            Ok(rounded) if rounded >= limit => {
+                self.plain(mantissa / limit) + "e" + &(exponent + step).to_string()
+            }
+            _ => text + "e" + &exponent.to_string(),
+        }
+    }
+}
+
+/// Inserts `separator` between every three digits of the integer part of `number`
+fn group_thousands(number: &str, separator: char) -> String {
+    let (sign, rest) = match number.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", number),
+    };
+    let (integer, fraction) = match rest.find('.') {
+        Some(idx) => rest.split_at(idx),
+        None => (rest, ""),
+    };
+
+    let mut grouped = String::with_capacity(number.len() + integer.len() / 3);
+    for (idx, digit) in integer.chars().enumerate() {
+        if idx > 0 && (integer.len() - idx).is_multiple_of(3) {
+            grouped.push(separator);
+        }
+        grouped.push(digit);
+    }
+
+    format!("{}{}{}", sign, grouped, fraction)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Notation {
+    #[default]
+    Plain,
+    Scientific,
+    Engineering,
+}
+
+impl FromStr for Notation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "plain" => Ok(Self::Plain),
+            "scientific" | "sci" => Ok(Self::Scientific),
+            "engineering" | "eng" => Ok(Self::Engineering),
+            other => Err(format!(
+                "Unknown notation. Ensure it is one of `plain`, `scientific` or `engineering` (was: {})",
+                other
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbers(args: &[&str]) -> NumberFormat {
+        NumberFormat::from_iter_safe(std::iter::once("numbers").chain(args.iter().copied()))
+            .unwrap()
+    }
+
+    #[test]
+    fn format_numbers() {
+        let data = vec![
+            (vec![], 1234.5, "1234.5"),
+            (vec!["--decimals", "2"], 1.23456, "1.23"),
+            (vec!["--thousands", ","], -1234567.25, "-1,234,567.25"),
+            (vec!["--thousands", "_", "--decimals", "0"], 999.9, "1_000"),
+            (vec!["--notation", "scientific"], 1500.0, "1.5e3"),
+            (
+                vec!["--notation", "sci", "--decimals", "1"],
+                0.0099,
+                "9.9e-3",
+            ),
+            (vec!["--notation", "sci", "--decimals", "0"], 9.6, "1e1"),
+            (vec!["--notation", "engineering"], 15000.0, "15e3"),
+            (vec!["--notation", "eng"], 0.00025, "250e-6"),
+            (vec!["--notation", "eng"], 0.0, "0e0"),
+            (vec!["--width", "6"], 42.0, "    42"),
+            (vec![], f64::INFINITY, "inf"),
+        ];
+
+        for (args, number, expected) in data {
+            assert_eq!(expected, numbers(&args).format(number, ""), "{:?}", args);
+        }
+    }
+}
@@ -0,0 +1,437 @@
+/*
+ * This Source Code Form is subject to the terms of
+ * the Mozilla Public License, v. 2.0. If a copy of
+ * the MPL was not distributed with this file, You
+ * can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use crate::{input::Record, notation::NumberFormat};
+use hanower::{Bucket, Unit};
+use std::{borrow::Cow, io::Write, str::FromStr};
+
+/// How every subcommand writes its results
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Values separated by spaces, one line per group
+    Words,
+    /// One value per line, groups separated by an empty line
+    Lines,
+    /// Every value terminated by a NUL byte
+    Nul,
+    /// A header followed by one row per value, including metadata
+    Csv,
+    /// A single array of objects, including metadata
+    Json,
+    /// One object per line, including metadata
+    Jsonl,
+    /// Aligned columns with a header, including metadata
+    Table,
+}
+
+impl Format {
+    /// Whether this format has room for metadata, such as bucket bounds
+    fn structured(self) -> bool {
+        matches!(self, Self::Csv | Self::Json | Self::Jsonl | Self::Table)
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "words" => Ok(Self::Words),
+            "lines" => Ok(Self::Lines),
+            "nul" => Ok(Self::Nul),
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            "jsonl" => Ok(Self::Jsonl),
+            "table" => Ok(Self::Table),
+            other => Err(format!(
+                "Unknown format. Ensure it is one of `words`, `lines`, `nul`, `csv`, `json`, `jsonl` or `table` (was: {})",
+                other
+            )),
+        }
+    }
+}
+
+/// A single field of output
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(f64),
+    /// A number of an interval with integer bounds, printed without decimals unless
+    /// they were asked for. JSON formats keep the number as it is
+    Whole(f64),
+    /// A count or index, which JSON formats keep integral
+    Integer(u64),
+    /// A whole number which may be negative, such as an exit code
+    Signed(i64),
+    /// A number in base units, printed in `Unit` such as `1.5KiB`.
+    /// JSON formats keep the number in base units
+    Quantity(f64, Unit),
+    Text(String),
+    /// A field with no value, such as the lower bound of the underflow group
+    Missing,
+}
+
+impl Value {
+    fn json(&self) -> serde_json::Value {
+        match self {
+            Self::Number(number) | Self::Whole(number) | Self::Quantity(number, _) => {
+                (*number).into()
+            }
+            Self::Integer(integer) => (*integer).into(),
+            Self::Signed(integer) => (*integer).into(),
+            Self::Text(text) => text.as_str().into(),
+            Self::Missing => serde_json::Value::Null,
+        }
+    }
+
+    fn text(&self, numbers: &NumberFormat) -> String {
+        match self {
+            Self::Number(number) => numbers.format(*number, ""),
+            Self::Whole(number) => numbers.or_decimals(Some(0)).format(*number, ""),
+            Self::Integer(integer) => integer.to_string(),
+            Self::Signed(integer) => integer.to_string(),
+            Self::Quantity(number, unit) => numbers.format(unit.from_base(*number), unit.suffix()),
+            Self::Text(text) => text.clone(),
+            Self::Missing => String::new(),
+        }
+    }
+
+    fn numeric(&self) -> bool {
+        matches!(
+            self,
+            Self::Number(_)
+                | Self::Whole(_)
+                | Self::Integer(_)
+                | Self::Signed(_)
+                | Self::Quantity(..)
+        )
+    }
+}
+
+impl From<f64> for Value {
+    fn from(number: f64) -> Self {
+        Self::Number(number)
+    }
+}
+
+impl From<u64> for Value {
+    fn from(integer: u64) -> Self {
+        Self::Integer(integer)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(integer: i64) -> Self {
+        Self::Signed(integer)
+    }
+}
+
+impl From<Record> for Value {
+    fn from(record: Record) -> Self {
+        Self::Text(record.line)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Self::Missing, Into::into)
+    }
+}
+
+/// A value, along with any metadata which structured formats print beside it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Row {
+    meta: Vec<(Cow<'static, str>, Value)>,
+    /// Column of the value, `value` unless renamed
+    name: Cow<'static, str>,
+    value: Value,
+}
+
+impl Row {
+    pub fn new(value: impl Into<Value>) -> Self {
+        Self {
+            meta: Vec::new(),
+            name: Cow::Borrowed("value"),
+            value: value.into(),
+        }
+    }
+
+    /// Adds a metadata field, printed before the value
+    pub fn with(mut self, name: impl Into<Cow<'static, str>>, value: impl Into<Value>) -> Self {
+        self.meta.push((name.into(), value.into()));
+        self
+    }
+
+    /// Renames the column of the value, such as to the axis of a grid it belongs to
+    pub fn named(self, name: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            name: name.into(),
+            ..self
+        }
+    }
+
+    /// Replaces the value, keeping any metadata
+    pub fn with_value(self, value: impl Into<Value>) -> Self {
+        Self {
+            value: value.into(),
+            ..self
+        }
+    }
+
+    /// Adds the index and bounds of `bucket`, or empty fields if there is none
+    pub fn with_bucket(self, bucket: Option<Bucket>) -> Self {
+        self.with("bucket", bucket.map(|b| b.index() as u64))
+            .with("low", bucket.map(|b| b.low()))
+            .with("high", bucket.map(|b| b.high()))
+    }
+
+    fn columns(&self) -> Vec<&str> {
+        self.meta
+            .iter()
+            .map(|(name, _)| name.as_ref())
+            .chain(std::iter::once(self.name.as_ref()))
+            .collect()
+    }
+
+    fn fields(&self) -> impl Iterator<Item = &Value> {
+        self.meta
+            .iter()
+            .map(|(_, value)| value)
+            .chain(std::iter::once(&self.value))
+    }
+
+    fn json(&self) -> serde_json::Value {
+        self.columns()
+            .into_iter()
+            .map(str::to_string)
+            .zip(self.fields().map(Value::json))
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    }
+}
+
+/// Writes rows to `dst` in a given Format.
+///
+/// Rows are collected into groups, such as one selection of `range --emit-every`
+/// or one bucket of `range --group`. Formats without metadata use groups to
+/// separate their output, and may print a group's label. Call `finish` once
+/// every row has been written.
+pub struct Output<'a> {
+    dst: &'a mut dyn Write,
+    format: Format,
+    numbers: NumberFormat,
+    /// Rows written so far, across every group
+    rows: usize,
+    /// Rows written in the current group, if one is open
+    group: Option<usize>,
+    groups: usize,
+    /// Header and rows of a table, which can only be aligned once complete
+    table: Vec<Vec<(String, bool)>>,
+}
+
+impl<'a> Output<'a> {
+    pub fn new(dst: &'a mut dyn Write, format: Format, numbers: NumberFormat) -> Self {
+        Self {
+            dst,
+            format,
+            numbers,
+            rows: 0,
+            group: None,
+            groups: 0,
+            table: Vec::new(),
+        }
+    }
+
+    /// Whether this Output prints metadata, so callers can skip building it
+    pub fn structured(&self) -> bool {
+        self.format.structured()
+    }
+
+    /// Ends the current group, if any, and starts a new one with an optional `label`
+    pub fn group(&mut self, label: Option<&str>) -> Result<(), color_eyre::eyre::Report> {
+        self.end_group()?;
+
+        match self.format {
+            Format::Words => {
+                if let Some(label) = label {
+                    write!(self.dst, "{}:", label)?;
+                }
+            }
+            Format::Lines if self.groups > 0 => writeln!(self.dst)?,
+            _ => {}
+        }
+
+        // A label counts as the first word of the group
+        self.group = Some(label.map_or(0, |_| 1));
+        self.groups += 1;
+
+        Ok(())
+    }
+
+    pub fn row(&mut self, row: Row) -> Result<(), color_eyre::eyre::Report> {
+        if self.group.is_none() {
+            self.group(None)?;
+        }
+
+        // Only structured formats have a place for a missing value
+        if row.value == Value::Missing && !self.structured() {
+            return Ok(());
+        }
+
+        let in_group = self.group.unwrap_or(0);
+        let value = row.value.text(&self.numbers);
+
+        match self.format {
+            Format::Words if in_group > 0 => write!(self.dst, " {}", value)?,
+            Format::Words => write!(self.dst, "{}", value)?,
+            Format::Lines => writeln!(self.dst, "{}", value)?,
+            Format::Nul => write!(self.dst, "{}\0", value)?,
+            Format::Csv => {
+                let mut writer = csv::Writer::from_writer(Vec::new());
+
+                if self.rows == 0 {
+                    writer.write_record(row.columns())?;
+                }
+                writer.write_record(row.fields().map(|field| field.text(&self.numbers)))?;
+                self.dst.write_all(&writer.into_inner()?)?;
+            }
+            Format::Json if self.rows == 0 => write!(self.dst, "[{}", row.json())?,
+            Format::Json => write!(self.dst, ",{}", row.json())?,
+            Format::Jsonl => writeln!(self.dst, "{}", row.json())?,
+            Format::Table => {
+                if self.rows == 0 {
+                    let header = row.columns().into_iter().map(|c| (c.to_string(), false));
+                    self.table.push(header.collect());
+                }
+
+                let cells = row
+                    .fields()
+                    .map(|field| (field.text(&self.numbers), field.numeric()));
+                self.table.push(cells.collect());
+            }
+        }
+
+        self.rows += 1;
+        self.group = Some(in_group + 1);
+
+        Ok(())
+    }
+
+    /// Ends the last group and completes the output
+    pub fn finish(mut self) -> Result<(), color_eyre::eyre::Report> {
+        self.end_group()?;
+
+        match self.format {
+            Format::Json if self.rows == 0 => writeln!(self.dst, "[]")?,
+            Format::Json => writeln!(self.dst, "]")?,
+            Format::Table => self.write_table()?,
+            _ => {}
+        }
+        self.dst.flush()?;
+
+        Ok(())
+    }
+
+    fn end_group(&mut self) -> Result<(), color_eyre::eyre::Report> {
+        if self.group.take().is_some() && self.format == Format::Words {
+            writeln!(self.dst)?;
+        }
+
+        // Keep partial results flowing, such as those of `range --emit-every`
+        if self.format != Format::Table {
+            self.dst.flush()?;
+        }
+
+        Ok(())
+    }
+
+    fn write_table(&mut self) -> Result<(), color_eyre::eyre::Report> {
+        let columns = self.table.iter().map(Vec::len).max().unwrap_or(0);
+        let widths: Vec<usize> = (0..columns)
+            .map(|column| {
+                self.table
+                    .iter()
+                    .filter_map(|row| row.get(column))
+                    .map(|(cell, _)| cell.chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        for row in self.table.iter() {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(widths.iter())
+                .map(|((cell, numeric), &width)| match numeric {
+                    true => format!("{:>width$}", cell, width = width),
+                    false => format!("{:<width$}", cell, width = width),
+                })
+                .collect();
+
+            writeln!(self.dst, "{}", cells.join("  ").trim_end())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(format: Format, groups: &[&[f64]]) -> String {
+        let mut buffer = Vec::new();
+        let mut out = Output::new(&mut buffer, format, NumberFormat::default());
+
+        for (idx, group) in groups.iter().enumerate() {
+            out.group(None).unwrap();
+            for &number in group.iter() {
+                out.row(Row::new(number).with("group", idx as u64)).unwrap();
+            }
+        }
+        out.finish().unwrap();
+
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn formats() {
+        let groups: &[&[f64]] = &[&[1.0, 2.5], &[10.0]];
+
+        let data = vec![
+            (Format::Words, "1 2.5\n10\n"),
+            (Format::Lines, "1\n2.5\n\n10\n"),
+            (Format::Nul, "1\u{0}2.5\u{0}10\u{0}"),
+            (Format::Csv, "group,value\n0,1\n0,2.5\n1,10\n"),
+            (
+                Format::Json,
+                "[{\"group\":0,\"value\":1.0},{\"group\":0,\"value\":2.5},{\"group\":1,\"value\":10.0}]\n",
+            ),
+            (Format::Table, "group  value\n    0      1\n    0    2.5\n    1     10\n"),
+        ];
+
+        for (format, expected) in data {
+            assert_eq!(expected, render(format, groups), "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn missing_values() {
+        let mut buffer = Vec::new();
+        let mut out = Output::new(&mut buffer, Format::Words, NumberFormat::default());
+
+        out.group(Some("[1, 10)")).unwrap();
+        out.row(Row::new(Value::Missing)).unwrap();
+        out.group(Some("[10, 100)")).unwrap();
+        out.row(Row::new(20.0)).unwrap();
+        out.finish().unwrap();
+
+        assert_eq!(
+            "[1, 10):\n[10, 100): 20\n",
+            String::from_utf8(buffer).unwrap()
+        );
+    }
+}
@@ -0,0 +1,215 @@
+/*
+ * This Source Code Form is subject to the terms of
+ * the Mozilla Public License, v. 2.0. If a copy of
+ * the MPL was not distributed with this file, You
+ * can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use std::{
+    fmt,
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// A point in time, as seconds since the Unix epoch
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Timestamp(pub f64);
+
+impl Timestamp {
+    pub fn now() -> Self {
+        let since_epoch = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs_f64())
+            .unwrap_or(0.0);
+
+        Self(since_epoch)
+    }
+
+    pub fn seconds(&self) -> f64 {
+        self.0
+    }
+}
+
+/// Parses either seconds since the Unix epoch, such as `1700000000`, or an
+/// RFC 3339 date and time, such as `2023-11-14T22:13:20Z` or `2023-11-14 22:13:20+01:00`.
+/// A bare date, such as `2023-11-14`, is midnight UTC
+impl FromStr for Timestamp {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        if let Ok(seconds) = s.parse::<f64>() {
+            return match seconds.is_finite() {
+                true => Ok(Self(seconds)),
+                false => Err(format!("Invalid timestamp (was: {})", s)),
+            };
+        }
+
+        parse_rfc3339(s)
+            .map(Self)
+            .ok_or_else(|| format!("Invalid timestamp. Expected seconds since the epoch or a date like `2023-11-14T22:13:20Z` (was: {})", s))
+    }
+}
+
+/// Writes this Timestamp as an RFC 3339 date and time in UTC, to the second
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seconds = self.0.floor() as i64;
+        let (year, month, day) = civil_from_days(seconds.div_euclid(86400));
+        let time = seconds.rem_euclid(86400);
+
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            year,
+            month,
+            day,
+            time / 3600,
+            time % 3600 / 60,
+            time % 60
+        )
+    }
+}
+
+fn parse_rfc3339(s: &str) -> Option<f64> {
+    let number = |s: &str| -> Option<i64> {
+        match s.chars().all(|c| c.is_ascii_digit()) && !s.is_empty() {
+            true => s.parse().ok(),
+            false => None,
+        }
+    };
+
+    let (date, time) = match s.find(['T', 't', ' ']) {
+        Some(idx) => (&s[..idx], Some(&s[idx + 1..])),
+        None => (s, None),
+    };
+
+    let mut parts = date.splitn(3, '-');
+    let year = number(parts.next()?)?;
+    let month = number(parts.next()?)?;
+    let day = number(parts.next()?)?;
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month as u32) as i64 {
+        return None;
+    }
+    let days = days_from_civil(year, month as u32, day as u32);
+
+    let seconds = match time {
+        None => 0.0,
+        Some(time) => {
+            let (clock, offset) = match time.rfind(['Z', 'z', '+', '-']) {
+                Some(idx) => time.split_at(idx),
+                None => (time, ""),
+            };
+
+            let offset = match offset {
+                "Z" | "z" => 0,
+                "" => return None,
+                offset => {
+                    let sign = if offset.starts_with('-') { -1 } else { 1 };
+                    let (hours, minutes) = offset[1..].split_at(offset[1..].find(':')?);
+                    sign * (number(hours)? * 3600 + number(&minutes[1..])? * 60)
+                }
+            };
+
+            let mut clock = clock.splitn(3, ':');
+            let hours = number(clock.next()?)?;
+            let minutes = number(clock.next()?)?;
+            let seconds = clock.next()?;
+            let (whole, fraction) = match seconds.find('.') {
+                Some(idx) => (&seconds[..idx], seconds[idx..].parse::<f64>().ok()?),
+                None => (seconds, 0.0),
+            };
+            let whole = number(whole)?;
+            if hours > 23 || minutes > 59 || whole > 60 {
+                return None;
+            }
+
+            (hours * 3600 + minutes * 60 + whole - offset) as f64 + fraction
+        }
+    };
+
+    Some(days as f64 * 86400.0 + seconds)
+}
+
+/// Returns the number of days in `month` (1-based) of `year`
+pub fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Returns the number of days since 1970-01-01 of a date in the proleptic Gregorian calendar
+pub fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146097 + day_of_era - 719468
+}
+
+/// Returns the `(year, month, day)` of a number of days since 1970-01-01
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_timestamps() {
+        let data = vec![
+            ("1700000000", 1700000000.0),
+            ("2023-11-14T22:13:20Z", 1700000000.0),
+            ("2023-11-14 23:13:20+01:00", 1700000000.0),
+            ("2023-11-14T22:13:20.5Z", 1700000000.5),
+            ("1970-01-01", 0.0),
+            ("2000-02-29", 951782400.0),
+            ("1969-12-31T23:59:59Z", -1.0),
+        ];
+
+        for (input, expected) in data {
+            assert_eq!(Ok(Timestamp(expected)), input.parse(), "{}", input);
+        }
+
+        for input in &[
+            "",
+            "yesterday",
+            "2023-02-29",
+            "2023-11-14T22:13:20",
+            "2023-13-01",
+        ] {
+            assert!(input.parse::<Timestamp>().is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn civil_round_trip() {
+        for days in (-800_000..800_000).step_by(997) {
+            let (year, month, day) = civil_from_days(days);
+
+            assert_eq!(days, days_from_civil(year, month, day));
+        }
+
+        assert_eq!("2023-11-14T22:13:20Z", Timestamp(1700000000.9).to_string());
+    }
+}
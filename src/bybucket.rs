@@ -0,0 +1,566 @@
+/*
+ * This Source Code Form is subject to the terms of
+ * the Mozilla Public License, v. 2.0. If a copy of
+ * the MPL was not distributed with this file, You
+ * can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+//! Incremental, per-bucket selection and aggregation of values.
+//!
+//! A [`ByBucket`] sorts each pushed item into its [`Bucket`] and hands it to a
+//! [`Reducer`], which decides what is kept. Only the reducer's state for each occupied
+//! bucket is held, so input can be streamed.
+
+use crate::{Bucket, Interval};
+use std::{collections::BTreeMap, marker::PhantomData};
+
+/// Intervals with at most this many buckets always use dense storage
+const DENSE_LIMIT: u64 = 4096;
+
+/// Anything which can be sorted into a bucket by a numeric key.
+pub trait Keyed {
+    /// Returns the value used to pick this item's bucket.
+    fn key(&self) -> f64;
+}
+
+impl Keyed for f64 {
+    fn key(&self) -> f64 {
+        *self
+    }
+}
+
+/// Reduces every item of a bucket into zero or more outputs.
+///
+/// Selectors, such as [`Min`] or [`First`], output the items themselves,
+/// while aggregates, such as [`Mean`] or [`Count`], output new numbers.
+pub trait Reducer<T> {
+    /// What is kept for each occupied bucket.
+    type State;
+    /// What is produced for each occupied bucket.
+    type Output;
+
+    /// Creates the state of a bucket from its first item.
+    fn start(&self, bucket: &Bucket, item: T) -> Self::State;
+
+    /// Adds a later item to the state of a bucket.
+    fn push(&self, bucket: &Bucket, state: &mut Self::State, item: T);
+
+    /// Produces the current output of a bucket.
+    fn finish(&self, state: &Self::State) -> Vec<Self::Output>;
+}
+
+/// Incrementally reduces the items of each bucket of an Interval,
+/// holding only the [`Reducer`] state of each occupied bucket
+#[derive(Debug)]
+pub struct ByBucket<T, R: Reducer<T>> {
+    interval: Interval,
+    reducer: R,
+    buckets: Buckets<R::State>,
+    _item: PhantomData<fn(T)>,
+}
+
+impl<T, R> ByBucket<T, R>
+where
+    T: Keyed,
+    R: Reducer<T>,
+{
+    /// Creates an empty ByBucket, which reduces each bucket of `interval` with `reducer`.
+    pub fn new(interval: Interval, reducer: R) -> Self {
+        Self {
+            interval,
+            reducer,
+            buckets: Buckets::new(interval.count()),
+            _item: PhantomData,
+        }
+    }
+
+    /// Returns the Interval this ByBucket was created with.
+    pub fn interval(&self) -> &Interval {
+        &self.interval
+    }
+
+    /// Offers a new item to the bucket it falls in, returning false
+    /// (and ignoring the item) if it falls outside the Interval.
+    pub fn push(&mut self, item: T) -> bool {
+        let bucket = match self
+            .interval
+            .bucket(item.key())
+            .and_then(|index| self.interval.bucket_bounds(index))
+        {
+            Some(bucket) => bucket,
+            None => return false,
+        };
+        let reducer = &self.reducer;
+
+        self.buckets.update(bucket.index(), |state| match state {
+            Some(state) => reducer.push(&bucket, state, item),
+            None => *state = Some(reducer.start(&bucket, item)),
+        });
+
+        true
+    }
+
+    /// Returns the current output of every occupied bucket, in bucket order.
+    pub fn results(&self) -> impl Iterator<Item = (Bucket, Vec<R::Output>)> + '_ {
+        self.buckets.iter().filter_map(move |(index, state)| {
+            self.interval
+                .bucket_bounds(index)
+                .map(|bucket| (bucket, self.reducer.finish(state)))
+        })
+    }
+
+    /// Returns the current output of every occupied bucket, flattened, in bucket order.
+    pub fn selection(&self) -> impl Iterator<Item = R::Output> + '_ {
+        self.buckets
+            .iter()
+            .flat_map(move |(_, state)| self.reducer.finish(state))
+    }
+}
+
+/// Per-bucket storage, which is sparse while few buckets are occupied so that
+/// memory is bounded by the number of input values rather than the bucket count
+#[derive(Debug)]
+enum Buckets<S> {
+    Dense(Vec<Option<S>>),
+    Sparse {
+        buckets: BTreeMap<usize, S>,
+        count: usize,
+    },
+}
+
+impl<S> Buckets<S> {
+    fn new(count: u64) -> Self {
+        match count {
+            count if count <= DENSE_LIMIT => Self::Dense(
+                std::iter::repeat_with(|| None)
+                    .take(count as usize)
+                    .collect(),
+            ),
+            count => Self::Sparse {
+                buckets: BTreeMap::new(),
+                count: count as usize,
+            },
+        }
+    }
+
+    fn update(&mut self, bucket: usize, f: impl FnOnce(&mut Option<S>)) {
+        match self {
+            Self::Dense(buckets) => {
+                if let Some(state) = buckets.get_mut(bucket) {
+                    f(state)
+                }
+            }
+            Self::Sparse { buckets, count } => {
+                let mut state = buckets.remove(&bucket);
+                f(&mut state);
+
+                if let Some(state) = state {
+                    buckets.insert(bucket, state);
+                }
+
+                // A map entry costs several times a Vec slot, so switch once
+                // a quarter of the buckets are occupied
+                if buckets.len() * 4 > *count {
+                    let mut dense: Vec<Option<S>> =
+                        std::iter::repeat_with(|| None).take(*count).collect();
+
+                    for (bucket, state) in std::mem::take(buckets) {
+                        dense[bucket] = Some(state);
+                    }
+
+                    *self = Self::Dense(dense)
+                }
+            }
+        }
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = (usize, &S)> + '_> {
+        match self {
+            Self::Dense(buckets) => Box::new(
+                buckets
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(index, state)| state.as_ref().map(|state| (index, state))),
+            ),
+            Self::Sparse { buckets, .. } => {
+                Box::new(buckets.iter().map(|(&index, state)| (index, state)))
+            }
+        }
+    }
+}
+
+/// Selects the first item of each bucket.
+#[derive(Debug, Clone, Copy)]
+pub struct First;
+
+impl<T: Clone> Reducer<T> for First {
+    type State = T;
+    type Output = T;
+
+    fn start(&self, _: &Bucket, item: T) -> T {
+        item
+    }
+
+    fn push(&self, _: &Bucket, _: &mut T, _: T) {}
+
+    fn finish(&self, state: &T) -> Vec<T> {
+        vec![state.clone()]
+    }
+}
+
+/// Selects the last item of each bucket.
+#[derive(Debug, Clone, Copy)]
+pub struct Last;
+
+impl<T: Clone> Reducer<T> for Last {
+    type State = T;
+    type Output = T;
+
+    fn start(&self, _: &Bucket, item: T) -> T {
+        item
+    }
+
+    fn push(&self, _: &Bucket, state: &mut T, item: T) {
+        *state = item
+    }
+
+    fn finish(&self, state: &T) -> Vec<T> {
+        vec![state.clone()]
+    }
+}
+
+/// Selects the item with the smallest key in each bucket, keeping the first on ties.
+#[derive(Debug, Clone, Copy)]
+pub struct Min;
+
+impl<T: Keyed + Clone> Reducer<T> for Min {
+    type State = T;
+    type Output = T;
+
+    fn start(&self, _: &Bucket, item: T) -> T {
+        item
+    }
+
+    fn push(&self, _: &Bucket, state: &mut T, item: T) {
+        if item.key() < state.key() {
+            *state = item
+        }
+    }
+
+    fn finish(&self, state: &T) -> Vec<T> {
+        vec![state.clone()]
+    }
+}
+
+/// Selects the item with the largest key in each bucket, keeping the first on ties.
+#[derive(Debug, Clone, Copy)]
+pub struct Max;
+
+impl<T: Keyed + Clone> Reducer<T> for Max {
+    type State = T;
+    type Output = T;
+
+    fn start(&self, _: &Bucket, item: T) -> T {
+        item
+    }
+
+    fn push(&self, _: &Bucket, state: &mut T, item: T) {
+        if item.key() > state.key() {
+            *state = item
+        }
+    }
+
+    fn finish(&self, state: &T) -> Vec<T> {
+        vec![state.clone()]
+    }
+}
+
+/// Selects the item closest to the lower fence of each bucket, keeping the first on ties.
+#[derive(Debug, Clone, Copy)]
+pub struct NearestLow;
+
+impl<T: Keyed + Clone> Reducer<T> for NearestLow {
+    type State = T;
+    type Output = T;
+
+    fn start(&self, _: &Bucket, item: T) -> T {
+        item
+    }
+
+    fn push(&self, bucket: &Bucket, state: &mut T, item: T) {
+        if (item.key() - bucket.low()).abs() < (state.key() - bucket.low()).abs() {
+            *state = item
+        }
+    }
+
+    fn finish(&self, state: &T) -> Vec<T> {
+        vec![state.clone()]
+    }
+}
+
+/// Selects the item closest to the upper fence of each bucket, keeping the first on ties.
+#[derive(Debug, Clone, Copy)]
+pub struct NearestHigh;
+
+impl<T: Keyed + Clone> Reducer<T> for NearestHigh {
+    type State = T;
+    type Output = T;
+
+    fn start(&self, _: &Bucket, item: T) -> T {
+        item
+    }
+
+    fn push(&self, bucket: &Bucket, state: &mut T, item: T) {
+        if (item.key() - bucket.high()).abs() < (state.key() - bucket.high()).abs() {
+            *state = item
+        }
+    }
+
+    fn finish(&self, state: &T) -> Vec<T> {
+        vec![state.clone()]
+    }
+}
+
+/// Selects the median item of each bucket, the lower of the two middle items
+/// when a bucket holds an even number of them.
+///
+/// Note: every item of a bucket is held until the end.
+#[derive(Debug, Clone, Copy)]
+pub struct Median;
+
+impl<T: Keyed + Clone> Reducer<T> for Median {
+    type State = Vec<T>;
+    type Output = T;
+
+    fn start(&self, _: &Bucket, item: T) -> Vec<T> {
+        vec![item]
+    }
+
+    fn push(&self, _: &Bucket, state: &mut Vec<T>, item: T) {
+        state.push(item)
+    }
+
+    fn finish(&self, state: &Vec<T>) -> Vec<T> {
+        let mut sorted: Vec<&T> = state.iter().collect();
+        sorted.sort_by(|a, b| a.key().total_cmp(&b.key()));
+
+        vec![sorted[(sorted.len() - 1) / 2].clone()]
+    }
+}
+
+/// Selects the `k` items with the largest keys in each bucket, in ascending order.
+#[derive(Debug, Clone, Copy)]
+pub struct TopK(pub usize);
+
+impl<T: Keyed + Clone> Reducer<T> for TopK {
+    /// Kept sorted in ascending order, at most `k` long
+    type State = Vec<T>;
+    type Output = T;
+
+    fn start(&self, bucket: &Bucket, item: T) -> Vec<T> {
+        let mut state = Vec::with_capacity(self.0.min(16));
+        self.push(bucket, &mut state, item);
+
+        state
+    }
+
+    fn push(&self, _: &Bucket, state: &mut Vec<T>, item: T) {
+        // Later items sort after equal keys, so ties keep the earliest
+        let idx = state.partition_point(|current| current.key() < item.key());
+        state.insert(idx, item);
+
+        if state.len() > self.0 {
+            state.remove(0);
+        }
+    }
+
+    fn finish(&self, state: &Vec<T>) -> Vec<T> {
+        state.clone()
+    }
+}
+
+/// Sums the keys of each bucket.
+#[derive(Debug, Clone, Copy)]
+pub struct Sum;
+
+impl<T: Keyed> Reducer<T> for Sum {
+    type State = f64;
+    type Output = f64;
+
+    fn start(&self, _: &Bucket, item: T) -> f64 {
+        item.key()
+    }
+
+    fn push(&self, _: &Bucket, state: &mut f64, item: T) {
+        *state += item.key()
+    }
+
+    fn finish(&self, state: &f64) -> Vec<f64> {
+        vec![*state]
+    }
+}
+
+/// Counts the items of each bucket.
+#[derive(Debug, Clone, Copy)]
+pub struct Count;
+
+impl<T> Reducer<T> for Count {
+    type State = u64;
+    type Output = f64;
+
+    fn start(&self, _: &Bucket, _: T) -> u64 {
+        1
+    }
+
+    fn push(&self, _: &Bucket, state: &mut u64, _: T) {
+        *state += 1
+    }
+
+    fn finish(&self, state: &u64) -> Vec<f64> {
+        vec![*state as f64]
+    }
+}
+
+/// Averages the keys of each bucket.
+#[derive(Debug, Clone, Copy)]
+pub struct Mean;
+
+impl<T: Keyed> Reducer<T> for Mean {
+    /// The running mean, and the number of items it covers
+    type State = (f64, u64);
+    type Output = f64;
+
+    fn start(&self, _: &Bucket, item: T) -> (f64, u64) {
+        (item.key(), 1)
+    }
+
+    fn push(&self, _: &Bucket, (mean, count): &mut (f64, u64), item: T) {
+        // Incremental mean, which avoids overflowing a running sum
+        *count += 1;
+        *mean += (item.key() - *mean) / *count as f64;
+    }
+
+    fn finish(&self, &(mean, _): &(f64, u64)) -> Vec<f64> {
+        vec![mean]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Spacing;
+
+    const INPUT: [f64; 10] = [5.0, 3.0, 900.0, 1e6, 42.0, 7.0, 6.5, 999_999.0, 0.5, 3.0];
+
+    fn reduce<R>(interval: Interval, reducer: R) -> Vec<f64>
+    where
+        R: Reducer<f64, Output = f64>,
+    {
+        let mut by = ByBucket::new(interval, reducer);
+        INPUT.iter().for_each(|&item| {
+            by.push(item);
+        });
+
+        by.selection().collect()
+    }
+
+    #[test]
+    fn reducers() {
+        // Buckets are 1..10, 10..100, 100..1000, 1000..10000, ..
+        let interval = Interval::new(1.0, 1e6 + 1.0, 6).unwrap();
+
+        assert_eq!(vec![5.0, 42.0, 900.0, 1e6], reduce(interval, First));
+        assert_eq!(vec![3.0, 42.0, 900.0, 999_999.0], reduce(interval, Last));
+        assert_eq!(vec![3.0, 42.0, 900.0, 999_999.0], reduce(interval, Min));
+        assert_eq!(vec![7.0, 42.0, 900.0, 1e6], reduce(interval, Max));
+        assert_eq!(vec![5.0, 42.0, 900.0, 999_999.0], reduce(interval, Median));
+        assert_eq!(vec![5.0, 1.0, 1.0, 2.0], reduce(interval, Count));
+        assert_eq!(vec![24.5, 42.0, 900.0, 1_999_999.0], reduce(interval, Sum));
+        assert_eq!(
+            vec![4.9, 42.0, 900.0, 1_999_999.0 / 2.0],
+            reduce(interval, Mean)
+        );
+        assert_eq!(
+            vec![6.5, 7.0, 42.0, 900.0, 999_999.0, 1e6],
+            reduce(interval, TopK(2))
+        );
+        assert_eq!(
+            vec![3.0, 42.0, 900.0, 999_999.0],
+            reduce(interval, NearestLow)
+        );
+        assert_eq!(vec![7.0, 42.0, 900.0, 1e6], reduce(interval, NearestHigh));
+    }
+
+    #[test]
+    fn results_carry_bounds() {
+        let interval = Interval::with_spacing(0.0, 10.0, 5, Spacing::Linear).unwrap();
+        let mut by = ByBucket::new(interval, Count);
+
+        for &item in [1.0, 2.5, 3.0, 9.0, 10.0, -1.0].iter() {
+            by.push(item);
+        }
+
+        let results: Vec<_> = by
+            .results()
+            .map(|(bucket, out)| (bucket.index(), bucket.low(), bucket.high(), out))
+            .collect();
+
+        assert_eq!(
+            vec![
+                (0, 0.0, 2.0, vec![1.0]),
+                (1, 2.0, 4.0, vec![2.0]),
+                (4, 8.0, 10.0, vec![1.0]),
+            ],
+            results
+        );
+    }
+
+    #[test]
+    fn sparse_matches_dense() {
+        let interval = Interval::new(1.0, 1e6, 64).unwrap();
+
+        let mut dense = ByBucket::new(interval, Max);
+        let mut sparse = ByBucket::new(interval, Max);
+        sparse.buckets = Buckets::Sparse {
+            buckets: BTreeMap::new(),
+            count: interval.count() as usize,
+        };
+
+        for &item in INPUT.iter() {
+            dense.push(item);
+            sparse.push(item);
+        }
+
+        assert!(matches!(sparse.buckets, Buckets::Sparse { .. }));
+        assert_eq!(
+            dense.selection().collect::<Vec<_>>(),
+            sparse.selection().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn sparse_promotes_to_dense() {
+        let interval = Interval::with_spacing(0.0, 10_000.0, 10_000, Spacing::Linear).unwrap();
+        let mut by = ByBucket::new(interval, Max);
+
+        for item in 0..5_000 {
+            by.push(item as f64 * 2.0);
+        }
+
+        assert!(matches!(by.buckets, Buckets::Dense(_)));
+        assert_eq!(5_000, by.selection().count());
+    }
+
+    #[test]
+    fn huge_count_is_cheap() {
+        let interval = Interval::new(1.0, 1e12, 1_000_000_000).unwrap();
+        let mut by = ByBucket::new(interval, Min);
+
+        for &item in [1.0, 2.0, 1e6, 1e9].iter() {
+            by.push(item);
+        }
+
+        assert!(matches!(by.buckets, Buckets::Sparse { .. }));
+        assert_eq!(vec![1.0, 2.0, 1e6, 1e9], by.selection().collect::<Vec<_>>());
+    }
+}
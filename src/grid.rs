@@ -0,0 +1,352 @@
+/*
+ * This Source Code Form is subject to the terms of
+ * the Mozilla Public License, v. 2.0. If a copy of
+ * the MPL was not distributed with this file, You
+ * can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+//! Multi-dimensional grids, the cartesian product of the fences of several
+//! [`Interval`]s, such as a matrix of thread counts and payload sizes.
+
+use crate::Interval;
+use std::fmt;
+
+/// A named [`Interval`], one dimension of a [`Grid`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Axis {
+    name: String,
+    interval: Interval,
+}
+
+impl Axis {
+    /// Creates an Axis called `name` over the fences of `interval`.
+    pub fn new(name: impl Into<String>, interval: Interval) -> Self {
+        Self {
+            name: name.into(),
+            interval,
+        }
+    }
+
+    /// Returns the `name` value.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the `interval` value.
+    pub fn interval(&self) -> Interval {
+        self.interval
+    }
+
+    /// Returns the number of fences along this Axis, `count + 1`.
+    pub fn len(&self) -> usize {
+        self.interval.intervals().len()
+    }
+
+    /// Always false, as every Axis has at least its `low` and `high` fences.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+}
+
+/// The cartesian product of the fences of one or more [`Axis`], which can be
+/// iterated lazily or indexed directly.
+///
+/// Points are numbered from 0 with the last Axis varying fastest, so a Grid
+/// can be resumed from any index with [`Grid::point`] or `iter().skip(index)`.
+///
+/// ```
+/// use hanower::{Axis, Grid, Interval};
+///
+/// let grid = Grid::new(vec![
+///     Axis::new("threads", Interval::new(1.0, 100.0, 2).unwrap()),
+///     Axis::new("size", "0..=1/1:linear".parse().unwrap()),
+/// ])
+/// .unwrap();
+///
+/// assert_eq!(6, grid.len());
+/// assert_eq!(Some(vec![10.0, 1.0]), grid.point(3));
+/// assert_eq!(Some(3), grid.index(&[1, 1]));
+/// ```
+///
+/// With the `serde` feature enabled, deserialized Grids are
+/// validated by [`Grid::new`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "GridSpec"))]
+pub struct Grid {
+    axes: Vec<Axis>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    len: usize,
+}
+
+impl Grid {
+    /// Creates a Grid over `axes`, which must be named uniquely, and whose
+    /// number of points must fit in a `usize`.
+    pub fn new(axes: Vec<Axis>) -> Result<Self, GridError> {
+        if axes.is_empty() {
+            return Err(GridError::NoAxes);
+        }
+
+        for (idx, axis) in axes.iter().enumerate() {
+            if axes[..idx].iter().any(|other| other.name == axis.name) {
+                return Err(GridError::DuplicateName(axis.name.clone()));
+            }
+        }
+
+        let len = axes
+            .iter()
+            .try_fold(1usize, |len, axis| len.checked_mul(axis.len()))
+            .ok_or(GridError::TooLarge)?;
+
+        Ok(Self { axes, len })
+    }
+
+    /// Returns the `axes` value.
+    pub fn axes(&self) -> &[Axis] {
+        &self.axes
+    }
+
+    /// Returns the number of points in this Grid.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Always false, as every Grid has at least one Axis with at least two fences.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Returns the fence index along each Axis of the point at `index`.
+    pub fn fences(&self, index: usize) -> Option<Vec<u64>> {
+        if index >= self.len {
+            return None;
+        }
+
+        let mut rest = index;
+        let mut fences = vec![0; self.axes.len()];
+        for (fence, axis) in fences.iter_mut().zip(self.axes.iter()).rev() {
+            *fence = (rest % axis.len()) as u64;
+            rest /= axis.len();
+        }
+
+        Some(fences)
+    }
+
+    /// Returns the index of the point with the given fence index along each Axis,
+    /// the inverse of [`Grid::fences`].
+    pub fn index(&self, fences: &[u64]) -> Option<usize> {
+        if fences.len() != self.axes.len() {
+            return None;
+        }
+
+        self.axes
+            .iter()
+            .zip(fences.iter())
+            .try_fold(0usize, |index, (axis, &fence)| match fence {
+                fence if fence < axis.len() as u64 => Some(index * axis.len() + fence as usize),
+                _ => None,
+            })
+    }
+
+    /// Returns the point at `index`, one value per Axis.
+    pub fn point(&self, index: usize) -> Option<Vec<f64>> {
+        let fences = self.fences(index)?;
+
+        self.axes
+            .iter()
+            .zip(fences)
+            .map(|(axis, fence)| axis.interval.fence(fence))
+            .collect()
+    }
+
+    /// Returns an iterator of lazily evaluated points, from index 0 to `len - 1`.
+    pub fn iter(&self) -> GridIter<'_> {
+        GridIter {
+            grid: self,
+            front: 0,
+            back: self.len,
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a Grid {
+    type Item = Vec<f64>;
+    type IntoIter = GridIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Unvalidated [`Grid`] fields, as read by a deserializer.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct GridSpec {
+    axes: Vec<Axis>,
+}
+
+#[cfg(feature = "serde")]
+impl std::convert::TryFrom<GridSpec> for Grid {
+    type Error = GridError;
+
+    fn try_from(spec: GridSpec) -> Result<Self, Self::Error> {
+        Self::new(spec.axes)
+    }
+}
+
+/// An iterator over the points of a [`Grid`], see [`Grid::iter`].
+///
+/// Skipping ahead with `nth` is constant time, as each point is computed from its index.
+#[derive(Debug, Clone)]
+pub struct GridIter<'a> {
+    grid: &'a Grid,
+    // Index of the next point from the front
+    front: usize,
+    // One past the index of the next point from the back
+    back: usize,
+}
+
+impl Iterator for GridIter<'_> {
+    type Item = Vec<f64>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front >= self.back {
+            return None;
+        }
+
+        self.front += 1;
+        self.grid.point(self.front - 1)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back.saturating_sub(self.front);
+
+        (len, Some(len))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.front = self.front.saturating_add(n).min(self.back);
+
+        self.next()
+    }
+}
+
+impl DoubleEndedIterator for GridIter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front >= self.back {
+            return None;
+        }
+
+        self.back -= 1;
+        self.grid.point(self.back)
+    }
+}
+
+impl ExactSizeIterator for GridIter<'_> {}
+
+impl std::iter::FusedIterator for GridIter<'_> {}
+
+/// Error kinds for creating a [`Grid`].
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GridError {
+    /// Occurs when the user provides no axes.
+    NoAxes,
+    /// Occurs when two axes share a name.
+    DuplicateName(String),
+    /// Occurs when the number of points does not fit in a `usize`.
+    TooLarge,
+}
+
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoAxes => write!(f, "Invalid grid. Ensure at least one axis is given"),
+            Self::DuplicateName(name) => write!(
+                f,
+                "Invalid grid. Ensure every axis has a different name (was: {})",
+                name
+            ),
+            Self::TooLarge => write!(
+                f,
+                "Invalid grid. Ensure the product of every axis' `count + 1` is <= {}",
+                usize::MAX
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GridError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid() -> Grid {
+        Grid::new(vec![
+            Axis::new("a", "1..=100/2".parse().unwrap()),
+            Axis::new("b", "0..=1/1:linear".parse().unwrap()),
+            Axis::new("c", "-1..=1/2:linear".parse().unwrap()),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn points_in_order() {
+        let grid = grid();
+        let points: Vec<Vec<f64>> = grid.iter().collect();
+
+        assert_eq!(18, grid.len());
+        assert_eq!(18, points.len());
+        assert_eq!(vec![1.0, 0.0, -1.0], points[0]);
+        assert_eq!(vec![1.0, 0.0, 0.0], points[1]);
+        assert_eq!(vec![1.0, 1.0, -1.0], points[3]);
+        assert_eq!(vec![10.0, 0.0, -1.0], points[6]);
+        assert_eq!(vec![100.0, 1.0, 1.0], points[17]);
+
+        for (index, point) in points.iter().enumerate() {
+            let fences = grid.fences(index).unwrap();
+
+            assert_eq!(Some(index), grid.index(&fences));
+            assert_eq!(Some(point), grid.point(index).as_ref());
+        }
+        assert_eq!(None, grid.point(18));
+        assert_eq!(None, grid.index(&[0, 2, 0]));
+    }
+
+    #[test]
+    fn resume_and_reverse() {
+        let grid = grid();
+        let mut iter = grid.iter();
+
+        assert_eq!(18, iter.len());
+        assert_eq!(grid.point(10), iter.nth(10));
+        assert_eq!(7, iter.len());
+        assert_eq!(grid.point(17), iter.next_back());
+        assert_eq!(6, iter.count());
+
+        let reversed: Vec<Vec<f64>> = grid.iter().rev().collect();
+        assert_eq!(grid.point(0).as_ref(), reversed.last());
+    }
+
+    #[test]
+    fn grid_err() {
+        let axis = Axis::new("a", "1..=100/2".parse().unwrap());
+        let huge = Axis::new("huge", Interval::new(0.0, 1e12, 1 << 40).unwrap());
+
+        assert!(matches!(Grid::new(vec![]), Err(GridError::NoAxes)));
+        assert!(matches!(
+            Grid::new(vec![axis.clone(), axis.clone()]),
+            Err(GridError::DuplicateName(_))
+        ));
+        assert!(matches!(
+            Grid::new(vec![
+                axis,
+                huge.clone(),
+                Axis::new("again", huge.interval())
+            ]),
+            Err(GridError::TooLarge)
+        ));
+    }
+}
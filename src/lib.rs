@@ -12,36 +12,113 @@
 /// Used to keep results to 12 significant decimal places
 pub const SIGNIFICANT: f64 = 1e12;
 
+/// The largest `count` accepted by [`Interval::new`],
+/// past this point fence indexes can no longer be represented exactly as an `f64`
+pub const MAX_COUNT: u64 = 1 << f64::MANTISSA_DIGITS;
+
 use std::fmt;
 
+mod bybucket;
+mod grid;
+mod rotation;
+mod snap;
+mod spec;
+mod units;
+
+pub use bybucket::{
+    ByBucket, Count, First, Keyed, Last, Max, Mean, Median, Min, NearestHigh, NearestLow, Reducer,
+    Sum, TopK,
+};
+pub use grid::{Axis, Grid, GridError, GridIter};
+pub use rotation::{Rotation, RotationError, MAX_SETS};
+pub use snap::{ParseRoundingError, Rounding};
+pub use spec::ParseIntervalError;
+pub use units::{ParseUnitError, Quantity, Unit, UnitFamily};
+
 /// Used to create and work with intervals which are calculated from the user-input CLI values.
 ///
 /// - `low` is the starting point of the section from which to find intervals
 /// - `high` is the inclusive end point of the section from which to find intervals
 /// - `count` is the total number of desired intervals to be calculated
 ///    - must be a minimum of 1 (prints interval of `low` to `high`)
+/// - `spacing` is how fences are distributed between `low` and `high`
 ///
 /// Note: values are rounded to 12 significant decimal places.
-#[derive(Debug, Clone, Copy)]
+///
+/// Intervals can also be parsed from, and displayed as, a compact spec
+/// such as `1..=1024/10` or `-19..12/3:linear`.
+///
+/// With the `serde` feature enabled, deserialized Intervals are
+/// validated by [`Interval::new`], so an invalid spec is rejected
+/// at deserialize time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "IntervalSpec"))]
 pub struct Interval {
     low: f64,
     high: f64,
     count: u64,
+    spacing: Spacing,
 }
 
 impl Interval {
     /// Creates a new Interval, with the range `low..=high`,
-    /// split into `count` number of intervals.
+    /// split into `count` number of logarithmically spaced intervals.
+    ///
+    /// Refuses any input that would produce NaN, infinite, or
+    /// indistinguishable fences.
     pub fn new(low: f64, high: f64, count: u64) -> Result<Self, IntervalError> {
-        if low >= high {
+        Self::with_spacing(low, high, count, Spacing::default())
+    }
+
+    /// Creates a new Interval, like [`Interval::new`], with the given `spacing`.
+    pub fn with_spacing(
+        low: f64,
+        high: f64,
+        count: u64,
+        spacing: Spacing,
+    ) -> Result<Self, IntervalError> {
+        if !low.is_finite() {
+            Err(IntervalError::NonFinite(low))
+        } else if !high.is_finite() {
+            Err(IntervalError::NonFinite(high))
+        } else if low >= high {
             Err(IntervalError::InvalidRange)
         } else if count < 1 {
             Err(IntervalError::LowCount(count))
+        } else if count > MAX_COUNT {
+            Err(IntervalError::CountTooLarge(count))
+        } else if !(high - low).is_finite() {
+            Err(IntervalError::NonFinite(high - low))
+        } else if !Self::is_resolvable(low, high, count, spacing) {
+            Err(IntervalError::PrecisionExhausted { low, high, count })
         } else {
-            Ok(Self { low, high, count })
+            Ok(Self {
+                low,
+                high,
+                count,
+                spacing,
+            })
         }
     }
 
+    /// Checks that the smallest step between two fences is large enough
+    /// to survive both `f64` rounding and rounding to [`SIGNIFICANT`].
+    fn is_resolvable(low: f64, high: f64, count: u64, spacing: Spacing) -> bool {
+        let span = high - low;
+        let (step, magnitude) = match spacing {
+            // Logarithmic steps grow as fast as the fences do, so the first step,
+            // next to `low`, is the hardest to resolve
+            Spacing::Log => (
+                (span.ln_1p() / count as f64).exp_m1(),
+                low.abs() + 2.0 + span.ln_1p(),
+            ),
+            Spacing::Linear => (span / count as f64, low.abs() + span),
+        };
+
+        step > 4.0 * f64::EPSILON * magnitude && step > 2.0 / SIGNIFICANT
+    }
+
     /// Returns the `low` value.
     pub fn low(&self) -> f64 {
         self.low
@@ -57,6 +134,11 @@ impl Interval {
         self.count
     }
 
+    /// Returns the `spacing` value.
+    pub fn spacing(&self) -> Spacing {
+        self.spacing
+    }
+
     /// Finds the bucket a given value exists in.
     ///
     /// A bucket refers to a range between two values, and including the starting value (similar to an interval).
@@ -65,15 +147,38 @@ impl Interval {
     /// and want to know which bucket the number `8` would be in. The output intervals
     /// would be `1 2 3 4 6 10`. The first bucket is then `2..<3`, next `3..<4`, etc.
     /// So, `8` is in the fourth bucket, between `6` and `10`.
+    ///
+    /// Buckets agree with the rounded fences of [`Interval::fence`], so each fence
+    /// is in the bucket it starts.
     pub fn bucket(&self, number: f64) -> Option<usize> {
-        if number < self.low() || number >= self.high() {
+        // Written as a negation so NaN never lands in a bucket
+        if !(number >= self.low() && number < self.high()) {
             return None;
         }
 
-        let bucket = f64::ln_1p(number - self.low())
-            / (f64::ln_1p(self.high() - self.low()) / self.count() as f64);
+        let bucket = match self.spacing() {
+            Spacing::Log => {
+                f64::ln_1p(number - self.low())
+                    / (f64::ln_1p(self.high() - self.low()) / self.count() as f64)
+            }
+            Spacing::Linear => {
+                (number - self.low()) / ((self.high() - self.low()) / self.count() as f64)
+            }
+        };
+
+        // Rounding can push values just below `high` into a nonexistent bucket
+        let mut index = (bucket.trunc() as u64).min(self.count() - 1);
+
+        // Fences are rounded to SIGNIFICANT, so values near one can land a bucket off
+        let iter = self.new_iter();
+        while index > 0 && number < iter.calculate_interval(index) {
+            index -= 1;
+        }
+        while index + 1 < self.count() && number >= iter.calculate_interval(index + 1) {
+            index += 1;
+        }
 
-        dbg!(Some(dbg!(bucket).trunc() as usize))
+        Some(index as usize)
     }
 
     // /// Iterates through a given list of numbers, and finds the appropriate
@@ -124,11 +229,117 @@ impl Interval {
         self.new_iter()
     }
 
+    /// Returns the fence at `index`, where `0` is `low` and `count` is `high`.
+    pub fn fence(&self, index: u64) -> Option<f64> {
+        match index {
+            index if index > self.count => None,
+            index => Some(self.new_iter().calculate_interval(index)),
+        }
+    }
+
+    /// Returns the bounds of the bucket at `index`, as found by [`Interval::bucket`].
+    pub fn bucket_bounds(&self, index: usize) -> Option<Bucket> {
+        let low = self.fence(index as u64)?;
+        let high = self.fence(index as u64 + 1)?;
+
+        Some(Bucket { index, low, high })
+    }
+
     fn new_iter(&self) -> IntervalIter {
         debug_assert!(self.low < self.high, "Low must be less than high");
         debug_assert!(self.count >= 1, "Interval count must be >= 1.");
 
-        IntervalIter::new(self.low, self.high, self.count)
+        IntervalIter::new(self.low, self.high, self.count, self.spacing)
+    }
+}
+
+/// A single bucket of an [`Interval`], the range `low..high` between two adjacent fences.
+///
+/// With the `serde` feature enabled, deserialized Buckets must
+/// have finite fences, with `low` < `high`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "BucketSpec"))]
+pub struct Bucket {
+    index: usize,
+    low: f64,
+    high: f64,
+}
+
+impl Bucket {
+    /// Returns the position of this bucket, starting from 0.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the inclusive lower fence of this bucket.
+    pub fn low(&self) -> f64 {
+        self.low
+    }
+
+    /// Returns the exclusive upper fence of this bucket.
+    pub fn high(&self) -> f64 {
+        self.high
+    }
+}
+
+/// How the fences of an [`Interval`] are distributed between `low` and `high`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Spacing {
+    /// Fences are dense near `low` and grow exponentially towards `high`.
+    #[default]
+    Log,
+    /// Fences are evenly spaced between `low` and `high`.
+    Linear,
+}
+
+/// Unvalidated [`Interval`] fields, as read by a deserializer.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct IntervalSpec {
+    low: f64,
+    high: f64,
+    count: u64,
+    #[serde(default)]
+    spacing: Spacing,
+}
+
+#[cfg(feature = "serde")]
+impl std::convert::TryFrom<IntervalSpec> for Interval {
+    type Error = IntervalError;
+
+    fn try_from(spec: IntervalSpec) -> Result<Self, Self::Error> {
+        Self::with_spacing(spec.low, spec.high, spec.count, spec.spacing)
+    }
+}
+
+/// Unvalidated [`Bucket`] fields, as read by a deserializer.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct BucketSpec {
+    index: usize,
+    low: f64,
+    high: f64,
+}
+
+#[cfg(feature = "serde")]
+impl std::convert::TryFrom<BucketSpec> for Bucket {
+    type Error = IntervalError;
+
+    fn try_from(spec: BucketSpec) -> Result<Self, Self::Error> {
+        let BucketSpec { index, low, high } = spec;
+
+        if !low.is_finite() {
+            Err(IntervalError::NonFinite(low))
+        } else if !high.is_finite() {
+            Err(IntervalError::NonFinite(high))
+        } else if low >= high {
+            Err(IntervalError::InvalidRange)
+        } else {
+            Ok(Self { index, low, high })
+        }
     }
 }
 
@@ -138,12 +349,14 @@ impl Interval {
 /// - `high` is the inclusive end point of the section from which to find intervals
 /// - `count` is the total number of desired intervals to be calculated
 ///    - must be a minimum of 1 (interval of `low` to `high`)
+/// - `spacing` is how fences are distributed between `low` and `high`
 /// - `idx_front` and `idx_back` are used to keep track of where the iterator is
 #[derive(Debug, Clone)]
 pub struct IntervalIter {
     low: f64,
     high: f64,
     count: u64,
+    spacing: Spacing,
 
     // Used by next()
     idx_front: u64,
@@ -152,11 +365,12 @@ pub struct IntervalIter {
 }
 
 impl IntervalIter {
-    fn new(low: f64, high: f64, count: u64) -> Self {
+    fn new(low: f64, high: f64, count: u64, spacing: Spacing) -> Self {
         Self {
             low,
             high,
             count,
+            spacing,
             idx_front: 0,
             idx_back: 0,
         }
@@ -173,9 +387,22 @@ impl IntervalIter {
             0 => self.low,
             i if i == self.count => self.high,
             _ => {
-                let nlog = (self.high - self.low).ln_1p() / self.count as f64;
-                let expo = (nlog * index as f64).exp();
-                ((expo + self.low - 1.0) * SIGNIFICANT).round() / SIGNIFICANT
+                let fence = match self.spacing {
+                    Spacing::Log => {
+                        let nlog = (self.high - self.low).ln_1p() / self.count as f64;
+                        let expo = (nlog * index as f64).exp();
+                        expo + self.low - 1.0
+                    }
+                    Spacing::Linear => {
+                        self.low + (self.high - self.low) * (index as f64 / self.count as f64)
+                    }
+                };
+
+                // Huge fences are already far coarser than SIGNIFICANT, and would overflow
+                match fence * SIGNIFICANT {
+                    scaled if scaled.is_finite() => scaled.round() / SIGNIFICANT,
+                    _ => fence,
+                }
             }
         }
     }
@@ -247,11 +474,19 @@ impl std::iter::FusedIterator for IntervalIter {}
 
 /// Error kinds for command line arguments.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum IntervalError {
     /// Occurs when the user provides a `count` value below 1.
     LowCount(u64),
     /// Occurs when the user gives a `low` value >= `high`.
     InvalidRange,
+    /// Occurs when `low`, `high` or the span between them is NaN or infinite.
+    NonFinite(f64),
+    /// Occurs when the user provides a `count` value above [`MAX_COUNT`].
+    CountTooLarge(u64),
+    /// Occurs when `count` splits `low..=high` so finely that
+    /// adjacent fences can no longer be told apart.
+    PrecisionExhausted { low: f64, high: f64, count: u64 },
 }
 
 impl fmt::Display for IntervalError {
@@ -265,25 +500,27 @@ impl fmt::Display for IntervalError {
             Self::InvalidRange => {
                 write!(f, "Invalid range. Ensure `start` value is less than `end`")
             }
+            Self::NonFinite(bad) => write!(
+                f,
+                "Invalid range. Ensure `start` and `end` are finite numbers (was: {})",
+                bad
+            ),
+            Self::CountTooLarge(bad) => write!(
+                f,
+                "Invalid count. Ensure `number` value is <= {} (was: {})",
+                MAX_COUNT, bad
+            ),
+            Self::PrecisionExhausted { low, high, count } => write!(
+                f,
+                "Invalid count. {}..={} cannot be split into {} distinct intervals",
+                low, high, count
+            ),
         }
     }
 }
 
 impl std::error::Error for IntervalError {}
 
-// fn read_csv(path: String) -> Result<Vec<f64>, Box<dyn Error>> {
-//     let mut reader = csv::Reader::from_path(path)?;
-//     let mut index_vec = vec![0.0];
-
-//     for result in reader.records() {
-//         let record = result?;
-//         let value: f64 = record[0].parse().unwrap();
-//         index_vec.push(value);
-//     }
-
-//     Ok(index_vec)
-// }
-
 #[cfg(test)]
 mod tests {
     #![allow(unused_imports)]
@@ -348,6 +585,151 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    /// Checks that NaN and infinite inputs are refused
+    fn non_finite_err() {
+        let bad = [
+            (f64::NAN, 10.0),
+            (1.0, f64::NAN),
+            (f64::NEG_INFINITY, 10.0),
+            (1.0, f64::INFINITY),
+            (-f64::MAX, f64::MAX),
+        ];
+
+        for &(low, high) in bad.iter() {
+            match Interval::new(low, high, 5) {
+                Err(IntervalError::NonFinite(_)) => {}
+                other => panic!(
+                    "{}..={} => Expected NonFinite, received {:?}",
+                    low, high, other
+                ),
+            }
+        }
+    }
+
+    #[test]
+    /// Checks that counts which cannot produce distinct fences are refused
+    fn degenerate_count_err() {
+        match Interval::new(1.0, 10.0, MAX_COUNT + 1) {
+            Err(IntervalError::CountTooLarge(_)) => {}
+            other => panic!("Expected CountTooLarge, received {:?}", other),
+        }
+
+        let exhausted = [(1.0, 10.0, 10_000_000_000_000), (1e15, 1e15 + 8.0, 100)];
+
+        for &(low, high, count) in exhausted.iter() {
+            match Interval::new(low, high, count) {
+                Err(IntervalError::PrecisionExhausted { .. }) => {}
+                other => panic!("Expected PrecisionExhausted, received {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    /// Checks that fine logarithmic steps next to a small `low` are accepted,
+    /// however large `high` is
+    fn fine_log_steps_ok() {
+        let interval = Interval::new(1.0, 1e12, 1_000_000_000).unwrap();
+
+        assert_eq!(Some(0), interval.bucket(1.0));
+        assert_eq!(Some(999_999_999), interval.bucket(1e12 - 1.0));
+    }
+
+    #[test]
+    /// Checks that every fence lands in the bucket it starts, despite fences being rounded
+    fn fences_start_their_buckets() {
+        let intervals = [
+            (1.0, 100.0, 4, Spacing::Log),
+            (1.0, 4096.0, 12, Spacing::Log),
+            (0.001, 86400.0, 30, Spacing::Log),
+            (-19.0, 12.0, 3, Spacing::Linear),
+            (0.1, 0.7, 6, Spacing::Linear),
+            (-5e6, 1e9, 1000, Spacing::Log),
+        ];
+
+        for &(low, high, count, spacing) in intervals.iter() {
+            let interval = Interval::with_spacing(low, high, count, spacing).unwrap();
+
+            for k in 0..count {
+                let fence = interval.fence(k).unwrap();
+                let bounds = interval.bucket_bounds(k as usize).unwrap();
+
+                assert_eq!(
+                    Some(k as usize),
+                    interval.bucket(fence),
+                    "{:?} {}",
+                    interval,
+                    k
+                );
+                assert_eq!(Some(k as usize), interval.bucket(bounds.low()));
+            }
+        }
+
+        assert_eq!(
+            Some(1),
+            Interval::new(1.0, 100.0, 4).unwrap().bucket(3.162277660168)
+        );
+    }
+
+    #[test]
+    /// Generates random inputs, checking that every accepted Interval yields
+    /// finite, strictly increasing fences and in range buckets
+    fn fuzz_accepted_intervals_are_monotonic() {
+        let mut rng = XorShift(0x2545_F491_4F6C_DD1D);
+        let mut accepted = 0;
+
+        for _ in 0..5_000 {
+            let low = rng.sample_f64();
+            let high = low + rng.sample_f64().abs();
+            let count = match rng.next() % 100 {
+                0 => 1 + rng.next() % 100_000,
+                _ => 1 + rng.next() % 2_000,
+            };
+            let spacing = match rng.next() % 2 {
+                0 => Spacing::Log,
+                _ => Spacing::Linear,
+            };
+
+            let interval = match Interval::with_spacing(low, high, count, spacing) {
+                Ok(interval) => interval,
+                Err(_) => continue,
+            };
+            accepted += 1;
+
+            let fences: Vec<f64> = interval.intervals().collect();
+            assert_eq!(fences.len() as u64, count + 1);
+
+            for pair in fences.windows(2) {
+                assert!(
+                    pair[0].is_finite() && pair[1].is_finite() && pair[0] < pair[1],
+                    "{:?} => non monotonic fences {} {}",
+                    interval,
+                    pair[0],
+                    pair[1]
+                );
+            }
+
+            for pair in fences.windows(2) {
+                let midpoint = pair[0] + (pair[1] - pair[0]) / 2.0;
+
+                if let Some(bucket) = interval.bucket(midpoint) {
+                    assert!(
+                        (bucket as u64) < count,
+                        "{:?} => bucket {}",
+                        interval,
+                        bucket
+                    );
+                }
+            }
+        }
+
+        assert!(
+            accepted > 1_000,
+            "Only {} intervals were accepted",
+            accepted
+        );
+    }
+
     #[test]
     /// Runs the program's computed intervals against a series of precomputed data sets,
     /// checking that all of the actual outputs match the expected values
@@ -433,6 +815,8 @@ mod tests {
             BucketTestData::new(Interval::new(30.0, 100.0, 10).unwrap(), 10.0 * 10.0, None),
             BucketTestData::new(Interval::new(-100.0, 100.0, 10).unwrap(), 0.0, Some(8)),
             BucketTestData::new(Interval::new(-100.0, 100.0, 10).unwrap(), -100.0, Some(0)),
+            BucketTestData::new(Interval::new(1.0, 10.0, 5).unwrap(), f64::NAN, None),
+            BucketTestData::new(Interval::new(0.5, 2.5, 4).unwrap(), 0.75, Some(0)),
         ];
 
         for test in data {
@@ -442,6 +826,97 @@ mod tests {
         }
     }
 
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_json_round_trip() -> TestResult {
+        for (interval, _) in test_data().into_iter() {
+            let json = serde_json::to_string(&interval)?;
+            let actual: Interval = serde_json::from_str(&json)?;
+
+            assert_eq!(interval.low(), actual.low());
+            assert_eq!(interval.high(), actual.high());
+            assert_eq!(interval.count(), actual.count());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_toml_round_trip() -> TestResult {
+        for (interval, _) in test_data().into_iter() {
+            let toml = toml::to_string(&interval)?;
+            let actual: Interval = toml::from_str(&toml)?;
+
+            assert_eq!(interval.low(), actual.low());
+            assert_eq!(interval.high(), actual.high());
+            assert_eq!(interval.count(), actual.count());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    /// Checks that invalid specs are refused by the deserializer, not just by `Interval::new`
+    fn serde_rejects_invalid_spec() {
+        let json = [
+            r#"{"low": 10.0, "high": 1.0, "count": 5}"#,
+            r#"{"low": 1.0, "high": 1.0, "count": 5}"#,
+            r#"{"low": 1.0, "high": 10.0, "count": 0}"#,
+        ];
+        let toml = [
+            "low = 10.0\nhigh = 1.0\ncount = 5",
+            "low = 1.0\nhigh = 1.0\ncount = 5",
+            "low = 1.0\nhigh = 10.0\ncount = 0",
+        ];
+
+        for spec in json.iter() {
+            assert!(serde_json::from_str::<Interval>(spec).is_err(), "{}", spec);
+        }
+
+        for spec in toml.iter() {
+            assert!(toml::from_str::<Interval>(spec).is_err(), "{}", spec);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    /// Checks that Buckets round trip, and that ones which no Interval could produce are refused
+    fn serde_bucket() -> TestResult {
+        let interval = Interval::new(1.0, 100.0, 4)?;
+
+        for bucket in (0..4).filter_map(|idx| interval.bucket_bounds(idx)) {
+            let json = serde_json::to_string(&bucket)?;
+            assert_eq!(bucket, serde_json::from_str::<Bucket>(&json)?);
+
+            let toml = toml::to_string(&bucket)?;
+            assert_eq!(bucket, toml::from_str::<Bucket>(&toml)?);
+        }
+
+        let invalid = [
+            r#"{"index": 0, "low": 10.0, "high": 1.0}"#,
+            r#"{"index": 0, "low": 1.0, "high": 1.0}"#,
+        ];
+        for spec in invalid.iter() {
+            assert!(serde_json::from_str::<Bucket>(spec).is_err(), "{}", spec);
+        }
+        assert!(toml::from_str::<Bucket>("index = 0\nlow = 1.0\nhigh = inf").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_error_round_trip() -> TestResult {
+        let json = serde_json::to_string(&IntervalError::LowCount(0))?;
+
+        match serde_json::from_str(&json)? {
+            IntervalError::LowCount(0) => Ok(()),
+            other => error!("Expected LowCount(0), received {:?}", other),
+        }
+    }
+
     // #[test]
     // fn first_in_buckets() {
     //     let expected: Option<Vec<i64>> = Some(vec![24, 46, 67]);
@@ -468,8 +943,40 @@ mod tests {
         }
     }
 
+    /// Minimal deterministic PRNG, used to generate fuzz inputs
+    struct XorShift(u64);
+
+    impl XorShift {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        /// Returns a signed value with a random magnitude between 1e-6 and 1e12,
+        /// or occasionally up to 1e300
+        fn sample_f64(&mut self) -> f64 {
+            let mantissa = (self.next() % 1_000_000) as f64 / 1e5;
+            let max_exponent = if self.next().is_multiple_of(10) {
+                307
+            } else {
+                19
+            };
+            let exponent = (self.next() % max_exponent) as i32 - 6;
+            let sign = if self.next().is_multiple_of(2) {
+                1.0
+            } else {
+                -1.0
+            };
+
+            sign * mantissa * 10f64.powi(exponent)
+        }
+    }
+
     /* --- HELPER FUNCTIONS --- */
 
+    #[allow(clippy::redundant_pattern_matching)]
     fn assert_output_length(actual: usize, expected: usize) -> TestResult {
         if let false = actual == expected {
             let msg = format!("Expected {} fences, but received: {}", expected, actual);
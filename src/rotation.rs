@@ -0,0 +1,173 @@
+/*
+ * This Source Code Form is subject to the terms of
+ * the Mozilla Public License, v. 2.0. If a copy of
+ * the MPL was not distributed with this file, You
+ * can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+//! Tower of Hanoi backup rotation, which keeps backups at roughly
+//! log-spaced ages using a fixed number of sets.
+
+use std::fmt;
+
+/// The largest number of sets a [`Rotation`] can cycle through.
+pub const MAX_SETS: u32 = u64::BITS;
+
+/// Picks which backup set to overwrite on each run.
+///
+/// Runs are numbered from 0. Set 0 is used every other run, set 1 every
+/// 4th run, set 2 every 8th run and so on, with the last two sets sharing
+/// the longest period of `2^(sets - 1)` runs. So with `sets` sets, the
+/// oldest backup is about `2^(sets - 1)` runs old.
+///
+/// ```
+/// use hanower::Rotation;
+///
+/// let rotation = Rotation::new(3).unwrap();
+/// let sets: Vec<u32> = rotation.schedule(0, 8).map(|(_, set)| set).collect();
+///
+/// assert_eq!(vec![0, 1, 0, 2, 0, 1, 0, 2], sets);
+/// ```
+///
+/// With the `serde` feature enabled, deserialized Rotations are
+/// validated by [`Rotation::new`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "RotationSpec"))]
+pub struct Rotation {
+    sets: u32,
+}
+
+impl Rotation {
+    /// Creates a Rotation over `sets` backup sets, which must be between 1 and [`MAX_SETS`].
+    pub fn new(sets: u32) -> Result<Self, RotationError> {
+        match sets {
+            0 => Err(RotationError::LowSets(sets)),
+            sets if sets > MAX_SETS => Err(RotationError::TooManySets(sets)),
+            sets => Ok(Self { sets }),
+        }
+    }
+
+    /// Returns the number of sets this Rotation cycles through.
+    pub fn sets(&self) -> u32 {
+        self.sets
+    }
+
+    /// Returns how many runs it takes for the least used sets to be overwritten.
+    pub fn period(&self) -> u64 {
+        1u64.checked_shl(self.sets - 1).unwrap_or(u64::MAX)
+    }
+
+    /// Returns the set to overwrite on `run`.
+    pub fn set_for(&self, run: u64) -> u32 {
+        // Run 0 is the 1st run, whose number has no trailing zeros
+        match run.checked_add(1) {
+            Some(n) => n.trailing_zeros().min(self.sets - 1),
+            None => self.sets - 1,
+        }
+    }
+
+    /// Returns each of `runs` runs starting at `start`, along with the set it overwrites.
+    pub fn schedule(&self, start: u64, runs: u64) -> impl Iterator<Item = (u64, u32)> + '_ {
+        (start..start.saturating_add(runs)).map(move |run| (run, self.set_for(run)))
+    }
+}
+
+/// Unvalidated [`Rotation`] fields, as read by a deserializer.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct RotationSpec {
+    sets: u32,
+}
+
+#[cfg(feature = "serde")]
+impl std::convert::TryFrom<RotationSpec> for Rotation {
+    type Error = RotationError;
+
+    fn try_from(spec: RotationSpec) -> Result<Self, Self::Error> {
+        Self::new(spec.sets)
+    }
+}
+
+/// Error kinds for creating a [`Rotation`].
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RotationError {
+    /// Occurs when the user provides a `sets` value below 1.
+    LowSets(u32),
+    /// Occurs when the user provides a `sets` value above [`MAX_SETS`].
+    TooManySets(u32),
+}
+
+impl fmt::Display for RotationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LowSets(bad) => write!(
+                f,
+                "Invalid sets. Ensure `sets` value is >= 1 (was: {})",
+                bad
+            ),
+            Self::TooManySets(bad) => write!(
+                f,
+                "Invalid sets. Ensure `sets` value is <= {} (was: {})",
+                MAX_SETS, bad
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RotationError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hanoi_schedule() {
+        let rotation = Rotation::new(5).unwrap();
+        let sets: Vec<u32> = rotation.schedule(0, 32).map(|(_, set)| set).collect();
+
+        assert_eq!(
+            vec![
+                0, 1, 0, 2, 0, 1, 0, 3, 0, 1, 0, 2, 0, 1, 0, 4, //
+                0, 1, 0, 2, 0, 1, 0, 3, 0, 1, 0, 2, 0, 1, 0, 4,
+            ],
+            sets
+        );
+        assert_eq!(16, rotation.period());
+    }
+
+    #[test]
+    fn single_set() {
+        let rotation = Rotation::new(1).unwrap();
+
+        assert!(rotation.schedule(0, 10).all(|(_, set)| set == 0));
+        assert_eq!(0, rotation.set_for(u64::MAX));
+    }
+
+    #[test]
+    fn sets_err() {
+        assert!(matches!(Rotation::new(0), Err(RotationError::LowSets(0))));
+        assert!(matches!(
+            Rotation::new(MAX_SETS + 1),
+            Err(RotationError::TooManySets(_))
+        ));
+
+        let widest = Rotation::new(MAX_SETS).unwrap();
+        assert_eq!(MAX_SETS - 1, widest.set_for(u64::MAX));
+        assert_eq!(1 << 63, widest.period());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_validated() {
+        let rotation = Rotation::new(5).unwrap();
+        let json = serde_json::to_string(&rotation).unwrap();
+        assert_eq!(rotation, serde_json::from_str(&json).unwrap());
+
+        for spec in &[r#"{"sets": 0}"#, r#"{"sets": 65}"#] {
+            assert!(serde_json::from_str::<Rotation>(spec).is_err(), "{}", spec);
+        }
+        assert!(toml::from_str::<Rotation>("sets = 0").is_err());
+    }
+}
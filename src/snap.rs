@@ -0,0 +1,196 @@
+/*
+ * This Source Code Form is subject to the terms of
+ * the Mozilla Public License, v. 2.0. If a copy of
+ * the MPL was not distributed with this file, You
+ * can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+//! Snapping the fences of an [`Interval`] to a fixed menu of allowed values,
+//! such as instance sizes or thread counts, or to whole numbers such as line numbers.
+
+use crate::Interval;
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+/// Which candidates a fence may snap to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rounding {
+    /// The largest candidate at or below the fence.
+    Floor,
+    /// The smallest candidate at or above the fence.
+    Ceil,
+    /// The candidate closest to the fence, preferring the lower one on a tie.
+    #[default]
+    Nearest,
+}
+
+impl Interval {
+    /// Maps each fence from [`Interval::intervals`] to a value from `candidates`,
+    /// as chosen by `rounding`.
+    ///
+    /// If `unique` is set, each candidate is used at most once, with fences
+    /// claiming candidates from `low` to `high`. A fence is `None` when no
+    /// (remaining) candidate satisfies `rounding`. Non-finite candidates are ignored.
+    pub fn snap(&self, candidates: &[f64], rounding: Rounding, unique: bool) -> Vec<Option<f64>> {
+        let mut available: Vec<f64> = candidates
+            .iter()
+            .copied()
+            .filter(|c| c.is_finite())
+            .collect();
+        available.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        available.dedup();
+
+        self.intervals()
+            .map(|fence| {
+                let index = nearest_index(&available, fence, rounding)?;
+
+                match unique {
+                    true => Some(available.remove(index)),
+                    false => Some(available[index]),
+                }
+            })
+            .collect()
+    }
+
+    /// Rounds each fence from [`Interval::intervals`] to an integer, as chosen by
+    /// `rounding`, dropping repeats.
+    ///
+    /// Fences closer together than one apart round to the same integer, so narrow
+    /// Intervals yield fewer than `count + 1` integers.
+    pub fn integers(&self, rounding: Rounding) -> Vec<i64> {
+        let mut integers: Vec<i64> = self
+            .intervals()
+            .map(|fence| match rounding {
+                Rounding::Floor => fence.floor(),
+                Rounding::Ceil => fence.ceil(),
+                // Ties go to the lower integer, as with `snap`
+                Rounding::Nearest => (fence - 0.5).ceil(),
+            } as i64)
+            .collect();
+        integers.dedup();
+
+        integers
+    }
+}
+
+/// Finds the index of the candidate in `sorted` which `fence` snaps to
+fn nearest_index(sorted: &[f64], fence: f64, rounding: Rounding) -> Option<usize> {
+    // Index of the first candidate above the fence
+    let above = sorted.partition_point(|&c| c <= fence);
+    let floor = above.checked_sub(1);
+    let ceil = match floor {
+        Some(idx) if sorted[idx] == fence => Some(idx),
+        _ if above < sorted.len() => Some(above),
+        _ => None,
+    };
+
+    match rounding {
+        Rounding::Floor => floor,
+        Rounding::Ceil => ceil,
+        Rounding::Nearest => match (floor, ceil) {
+            (Some(low), Some(high)) if sorted[high] - fence < fence - sorted[low] => Some(high),
+            (Some(low), _) => Some(low),
+            (None, high) => high,
+        },
+    }
+}
+
+impl FromStr for Rounding {
+    type Err = ParseRoundingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "floor" => Ok(Self::Floor),
+            "ceil" => Ok(Self::Ceil),
+            "nearest" => Ok(Self::Nearest),
+            other => Err(ParseRoundingError(other.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Rounding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Floor => write!(f, "floor"),
+            Self::Ceil => write!(f, "ceil"),
+            Self::Nearest => write!(f, "nearest"),
+        }
+    }
+}
+
+/// Error returned when parsing an unknown [`Rounding`].
+#[derive(Debug)]
+pub struct ParseRoundingError(String);
+
+impl fmt::Display for ParseRoundingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Unknown rounding. Ensure it is one of `floor`, `ceil` or `nearest` (was: {})",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ParseRoundingError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Spacing;
+
+    const SIZES: &[f64] = &[1.0, 2.0, 4.0, 8.0, 16.0, 48.0, 96.0];
+
+    #[test]
+    fn snap_rounding() {
+        // Fences: 1, 10, 100
+        let interval = Interval::new(1.0, 100.0, 2).unwrap();
+
+        let data = vec![
+            (Rounding::Floor, vec![Some(1.0), Some(8.0), Some(96.0)]),
+            (Rounding::Ceil, vec![Some(1.0), Some(16.0), None]),
+            (Rounding::Nearest, vec![Some(1.0), Some(8.0), Some(96.0)]),
+        ];
+
+        for (rounding, expected) in data {
+            assert_eq!(
+                expected,
+                interval.snap(SIZES, rounding, false),
+                "{}",
+                rounding
+            );
+        }
+    }
+
+    #[test]
+    fn snap_unique() {
+        // Fences: 1, ~1.26, ~1.59, 2
+        let interval = Interval::new(1.0, 2.0, 3).unwrap();
+
+        assert_eq!(
+            vec![Some(1.0), Some(1.0), Some(2.0), Some(2.0)],
+            interval.snap(SIZES, Rounding::Nearest, false)
+        );
+        assert_eq!(
+            vec![Some(1.0), Some(2.0), Some(4.0), Some(8.0)],
+            interval.snap(SIZES, Rounding::Nearest, true)
+        );
+        assert_eq!(
+            vec![Some(1.0), None, None, Some(2.0)],
+            interval.snap(SIZES, Rounding::Floor, true)
+        );
+    }
+
+    #[test]
+    fn distinct_integers() {
+        // Fences: 0, 2.5, 5, 7.5, 10
+        let interval = Interval::with_spacing(0.0, 10.0, 4, Spacing::Linear).unwrap();
+
+        assert_eq!(vec![0, 2, 5, 7, 10], interval.integers(Rounding::Nearest));
+        assert_eq!(vec![0, 3, 5, 8, 10], interval.integers(Rounding::Ceil));
+
+        // Fences: 1, ~1.26, ~1.59, 2
+        let interval = Interval::new(1.0, 2.0, 3).unwrap();
+
+        assert_eq!(vec![1, 2], interval.integers(Rounding::Nearest));
+    }
+}
@@ -0,0 +1,292 @@
+/*
+ * This Source Code Form is subject to the terms of
+ * the Mozilla Public License, v. 2.0. If a copy of
+ * the MPL was not distributed with this file, You
+ * can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+//! Compact textual form of an [`Interval`], such as `1..=1024/10` or `-19..=12/3:linear`.
+
+use crate::{Interval, IntervalError, Quantity, Spacing, Unit};
+use std::{fmt, str::FromStr};
+
+/// Parses an Interval spec of the form `low..=high/count[:spacing]`.
+///
+/// - the range is always inclusive, as `high` is the last fence. `low..high` is
+///   accepted as an alias of `low..=high`, so `-19..12/3:linear` still ends at `12`
+/// - `low` and `high` may carry a [`Unit`], such as `100ms..=30s/8`
+/// - `spacing` is either `log` (the default) or `linear`
+///
+/// The parsed values are validated by [`Interval::with_spacing`].
+impl FromStr for Interval {
+    type Err = ParseIntervalError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_with_unit(s).map(|(interval, _)| interval)
+    }
+}
+
+impl Interval {
+    /// Parses an Interval spec, as `str::parse` does, also returning
+    /// the unit the bounds were written in, if any.
+    pub fn parse_with_unit(s: &str) -> Result<(Self, Option<Unit>), ParseIntervalError> {
+        let s = s.trim();
+
+        let (range, spacing) = match s.rfind(':') {
+            Some(idx) => (&s[..idx], s[idx + 1..].parse()?),
+            None => (s, Spacing::default()),
+        };
+
+        let (range, count) = match range.rfind('/') {
+            Some(idx) => (&range[..idx], &range[idx + 1..]),
+            None => return Err(ParseIntervalError::MissingCount(s.to_string())),
+        };
+
+        let (low, high) = match range.find("..") {
+            Some(idx) => (&range[..idx], &range[idx + 2..]),
+            None => return Err(ParseIntervalError::MissingRange(s.to_string())),
+        };
+        // `..` is an alias of `..=`, as `high` is always a fence
+        let high = high.strip_prefix('=').unwrap_or(high);
+
+        let low = parse_number(low)?;
+        let high = parse_number(high)?;
+        let count = count
+            .trim()
+            .parse::<u64>()
+            .map_err(|_| ParseIntervalError::InvalidCount(count.to_string()))?;
+
+        Self::from_quantities(low, high, count, spacing)
+    }
+
+    /// Creates an Interval from bounds which may carry units, such as `512B` and `4KiB`,
+    /// also returning the unit to print its fences in, if any.
+    ///
+    /// The bounds must be written in compatible units, see [`ParseIntervalError::MixedUnits`].
+    pub fn from_quantities(
+        low: Quantity,
+        high: Quantity,
+        count: u64,
+        spacing: Spacing,
+    ) -> Result<(Self, Option<Unit>), ParseIntervalError> {
+        let unit = match (low.unit(), high.unit()) {
+            (Some(l), Some(h)) => {
+                Some(Unit::common(l, h).ok_or(ParseIntervalError::MixedUnits(l, h))?)
+            }
+            (l, h) => h.or(l),
+        };
+
+        Interval::with_spacing(low.value(), high.value(), count, spacing)
+            .map(|interval| (interval, unit))
+            .map_err(ParseIntervalError::Interval)
+    }
+}
+
+/// Writes this Interval as a spec which can be parsed back with `str::parse`.
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..={}/{}", self.low(), self.high(), self.count())?;
+
+        match self.spacing() {
+            spacing if spacing == Spacing::default() => Ok(()),
+            spacing => write!(f, ":{}", spacing),
+        }
+    }
+}
+
+impl FromStr for Spacing {
+    type Err = ParseIntervalError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "log" | "logarithmic" => Ok(Self::Log),
+            "linear" | "lin" => Ok(Self::Linear),
+            other => Err(ParseIntervalError::UnknownSpacing(other.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Spacing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Log => write!(f, "log"),
+            Self::Linear => write!(f, "linear"),
+        }
+    }
+}
+
+fn parse_number(s: &str) -> Result<Quantity, ParseIntervalError> {
+    s.parse::<Quantity>()
+        .map_err(|_| ParseIntervalError::InvalidNumber(s.to_string()))
+}
+
+/// Error kinds for parsing an [`Interval`] spec.
+#[derive(Debug)]
+pub enum ParseIntervalError {
+    /// Occurs when the spec has no `low..=high` range.
+    MissingRange(String),
+    /// Occurs when the spec has no `/count` suffix.
+    MissingCount(String),
+    /// Occurs when `low` or `high` is not a number.
+    InvalidNumber(String),
+    /// Occurs when `count` is not a positive integer.
+    InvalidCount(String),
+    /// Occurs when the `:spacing` suffix is not a known [`Spacing`].
+    UnknownSpacing(String),
+    /// Occurs when `low` and `high` are written in incompatible units.
+    MixedUnits(Unit, Unit),
+    /// Occurs when the parsed values do not form a valid [`Interval`].
+    Interval(IntervalError),
+}
+
+impl fmt::Display for ParseIntervalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingRange(spec) => write!(
+                f,
+                "Invalid spec. Expected a range like `1..=1024/10` (was: {})",
+                spec
+            ),
+            Self::MissingCount(spec) => write!(
+                f,
+                "Invalid spec. Expected a count after the range, like `1..=1024/10` (was: {})",
+                spec
+            ),
+            Self::InvalidNumber(bad) => write!(f, "Unable to parse as a number: {}", bad),
+            Self::InvalidCount(bad) => write!(f, "Unable to parse as a count: {}", bad),
+            Self::UnknownSpacing(bad) => write!(
+                f,
+                "Unknown spacing. Ensure it is one of `log` or `linear` (was: {})",
+                bad
+            ),
+            Self::MixedUnits(low, high) => write!(
+                f,
+                "Invalid range. `{}` and `{}` cannot be used in the same range",
+                low, high
+            ),
+            Self::Interval(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ParseIntervalError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Interval(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UnitFamily;
+
+    #[test]
+    fn parse_spec() {
+        let data = vec![
+            ("1..=1024/10", (1.0, 1024.0, 10, Spacing::Log)),
+            ("-19..=12/3:linear", (-19.0, 12.0, 3, Spacing::Linear)),
+            ("-20..=-10/4:log", (-20.0, -10.0, 4, Spacing::Log)),
+            ("1.5..=2e3/7", (1.5, 2000.0, 7, Spacing::Log)),
+            (" 0 ..= 256 / 2 ", (0.0, 256.0, 2, Spacing::Log)),
+            ("100ms..=30s/8", (0.1, 30.0, 8, Spacing::Log)),
+            (
+                "1KiB..=1MiB/10:linear",
+                (1024.0, 1048576.0, 10, Spacing::Linear),
+            ),
+        ];
+
+        for (spec, (low, high, count, spacing)) in data {
+            let interval: Interval = spec.parse().unwrap();
+
+            assert_eq!(
+                (low, high, count, spacing),
+                (
+                    interval.low(),
+                    interval.high(),
+                    interval.count(),
+                    interval.spacing()
+                ),
+                "{}",
+                spec
+            );
+        }
+    }
+
+    #[test]
+    fn parse_spec_err() {
+        let data = vec![
+            "",
+            "1..=1024",
+            "1/10",
+            "a..=1024/10",
+            "1..=1024/ten",
+            "1..=1024/-1",
+            "1..=1024/10:cubic",
+            "1024..=1/10",
+            "1..=1024/0",
+            "NaN..=1024/10",
+            "1s..=1KiB/10",
+        ];
+
+        for spec in data {
+            assert!(spec.parse::<Interval>().is_err(), "{}", spec);
+        }
+
+        // `..` is an alias of `..=`
+        for spec in &["1..1024/10", "-19..12/3:linear", "100ms..30s/8"] {
+            let inclusive = spec.replacen("..", "..=", 1);
+
+            assert_eq!(
+                inclusive.parse::<Interval>().unwrap(),
+                spec.parse::<Interval>().unwrap(),
+                "{}",
+                spec
+            );
+        }
+    }
+
+    #[test]
+    fn parse_spec_unit() {
+        let (_, unit) = Interval::parse_with_unit("0..=30s/8").unwrap();
+        assert_eq!(Some("s"), unit.map(|u| u.suffix()));
+
+        // Plain bytes fit either family of byte sizes
+        let (interval, unit) = Interval::parse_with_unit("512B..=4KiB/3").unwrap();
+        assert_eq!((512.0, 4096.0), (interval.low(), interval.high()));
+        assert_eq!(Some("KiB"), unit.map(|u| u.suffix()));
+
+        let (_, unit) = Interval::parse_with_unit("1KiB..=4096B/3").unwrap();
+        assert_eq!(Some(UnitFamily::BinaryBytes), unit.map(|u| u.family()));
+
+        for spec in &["1kB..=1MiB/3", "1s..=1KiB/3", "1B..=1k/3"] {
+            match Interval::parse_with_unit(spec) {
+                Err(ParseIntervalError::MixedUnits(..)) => {}
+                other => panic!("{} => Expected MixedUnits, received {:?}", spec, other),
+            }
+        }
+
+        let (_, unit) = Interval::parse_with_unit("1..=1024/10").unwrap();
+        assert_eq!(None, unit);
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let data = vec![
+            Interval::new(1.0, 1024.0, 10).unwrap(),
+            Interval::new(100.0, 1000.0, 15).unwrap(),
+            Interval::new(-11000.0, -1200.0, 16).unwrap(),
+            Interval::new(0.1, 0.30000000000000004, 2).unwrap(),
+            Interval::with_spacing(-19.0, 12.0, 3, Spacing::Linear).unwrap(),
+        ];
+
+        for interval in data {
+            let spec = interval.to_string();
+            let actual: Interval = spec.parse().unwrap();
+
+            assert_eq!(interval, actual, "{}", spec);
+        }
+    }
+}
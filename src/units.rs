@@ -0,0 +1,338 @@
+/*
+ * This Source Code Form is subject to the terms of
+ * the Mozilla Public License, v. 2.0. If a copy of
+ * the MPL was not distributed with this file, You
+ * can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+//! Human friendly units, such as `250ms`, `4KiB` or `1.5G`.
+//!
+//! Quantities are always converted to their family's base unit (seconds, bytes or
+//! a bare number), so they can be used directly as [`Interval`](crate::Interval) bounds.
+
+use crate::SIGNIFICANT;
+use std::{fmt, str::FromStr};
+
+/// A group of units which can be converted between each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnitFamily {
+    /// Time spans, with a base unit of seconds.
+    Duration,
+    /// Byte sizes in powers of 1000, such as `kB` and `MB`.
+    DecimalBytes,
+    /// Byte sizes in powers of 1024, such as `KiB` and `MiB`.
+    BinaryBytes,
+    /// Bare numbers with SI prefixes, such as `k` and `M`.
+    Si,
+}
+
+impl UnitFamily {
+    /// Returns every canonical unit of this family, from smallest to largest.
+    pub fn units(self) -> &'static [Unit] {
+        match self {
+            Self::Duration => DURATION,
+            Self::DecimalBytes => DECIMAL_BYTES,
+            Self::BinaryBytes => BINARY_BYTES,
+            Self::Si => SI,
+        }
+    }
+
+    /// Returns the unit of this family with a scale of 1.
+    pub fn base(self) -> Unit {
+        *self
+            .units()
+            .iter()
+            .find(|unit| unit.scale == 1.0)
+            .expect("every unit family has a base unit")
+    }
+
+    /// Picks the largest unit of this family which keeps `value` at or above 1,
+    /// falling back to the base unit for zero and the smallest unit for tiny values.
+    pub fn best_for(self, value: f64) -> Unit {
+        let value = value.abs();
+
+        if value == 0.0 {
+            return self.base();
+        }
+
+        self.units()
+            .iter()
+            .rev()
+            .find(|unit| unit.scale <= value)
+            .or_else(|| self.units().first())
+            .copied()
+            .expect("every unit family has at least one unit")
+    }
+}
+
+/// A single unit, such as `ms` or `KiB`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Unit {
+    suffix: &'static str,
+    scale: f64,
+    family: UnitFamily,
+}
+
+impl Unit {
+    const fn new(suffix: &'static str, scale: f64, family: UnitFamily) -> Self {
+        Self {
+            suffix,
+            scale,
+            family,
+        }
+    }
+
+    /// Returns the suffix this unit is written with.
+    pub fn suffix(&self) -> &'static str {
+        self.suffix
+    }
+
+    /// Returns how many base units make up one of this unit.
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    /// Returns the family this unit belongs to.
+    pub fn family(&self) -> UnitFamily {
+        self.family
+    }
+
+    /// Converts a `value` in base units to this unit.
+    ///
+    /// Note: values are rounded to 12 significant decimal places.
+    pub fn from_base(&self, value: f64) -> f64 {
+        let value = value / self.scale;
+
+        match value * SIGNIFICANT {
+            scaled if scaled.is_finite() => scaled.round() / SIGNIFICANT,
+            _ => value,
+        }
+    }
+
+    /// Writes a `value` in base units as a number in this unit, followed by its suffix.
+    pub fn display(&self, value: f64) -> String {
+        format!("{}{}", self.from_base(value), self.suffix)
+    }
+
+    /// Returns the unit to print a range from `low` to `high` in, or `None` if they
+    /// cannot be used in the same range. Plain bytes, `B`, fit either family of byte sizes.
+    pub fn common(low: Unit, high: Unit) -> Option<Unit> {
+        let bytes = |unit: Unit| {
+            matches!(
+                unit.family,
+                UnitFamily::DecimalBytes | UnitFamily::BinaryBytes
+            )
+        };
+
+        match (low, high) {
+            (low, high) if low.family == high.family => Some(high),
+            (low, high) if bytes(low) && bytes(high) && high.scale == 1.0 => Some(low),
+            (low, high) if bytes(low) && bytes(high) && low.scale == 1.0 => Some(high),
+            _ => None,
+        }
+    }
+
+    /// Looks up a unit by its suffix, including aliases such as `sec` or `KB`.
+    pub fn from_suffix(suffix: &str) -> Option<Self> {
+        let canonical = match suffix {
+            "µs" | "μs" => "us",
+            "min" => "m",
+            "sec" => "s",
+            "KB" => "kB",
+            "K" => "k",
+            other => other,
+        };
+
+        [DURATION, DECIMAL_BYTES, BINARY_BYTES, SI]
+            .iter()
+            .flat_map(|units| units.iter())
+            .find(|unit| unit.suffix == canonical && !unit.suffix.is_empty())
+            .copied()
+    }
+}
+
+impl FromStr for Unit {
+    type Err = ParseUnitError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_suffix(s.trim()).ok_or_else(|| ParseUnitError::UnknownUnit(s.to_string()))
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.suffix)
+    }
+}
+
+/// A number with an optional unit, such as `250ms`, `4KiB`, `1e6` or `-3`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quantity {
+    value: f64,
+    unit: Option<Unit>,
+}
+
+impl Quantity {
+    /// Returns the value in its family's base unit.
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+
+    /// Returns the unit this quantity was written in, if any.
+    pub fn unit(&self) -> Option<Unit> {
+        self.unit
+    }
+}
+
+impl FromStr for Quantity {
+    type Err = ParseUnitError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        if let Ok(value) = s.parse::<f64>() {
+            return Ok(Self { value, unit: None });
+        }
+
+        // The suffix is everything after the last digit or decimal point
+        let split = s
+            .rfind(|c: char| c.is_ascii_digit() || c == '.')
+            .map(|idx| idx + 1)
+            .ok_or_else(|| ParseUnitError::InvalidNumber(s.to_string()))?;
+        let (number, suffix) = s.split_at(split);
+
+        let unit = Unit::from_suffix(suffix.trim())
+            .ok_or_else(|| ParseUnitError::UnknownUnit(suffix.to_string()))?;
+        let number = number
+            .trim()
+            .parse::<f64>()
+            .map_err(|_| ParseUnitError::InvalidNumber(s.to_string()))?;
+
+        Ok(Self {
+            value: number * unit.scale,
+            unit: Some(unit),
+        })
+    }
+}
+
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.unit {
+            Some(unit) => write!(f, "{}", unit.display(self.value)),
+            None => write!(f, "{}", self.value),
+        }
+    }
+}
+
+/// Error kinds for parsing a [`Quantity`] or [`Unit`].
+#[derive(Debug)]
+pub enum ParseUnitError {
+    /// Occurs when the numeric part of a quantity is not a number.
+    InvalidNumber(String),
+    /// Occurs when a suffix is not a known unit.
+    UnknownUnit(String),
+}
+
+impl fmt::Display for ParseUnitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidNumber(bad) => write!(f, "Unable to parse as a number: {}", bad),
+            Self::UnknownUnit(bad) => write!(
+                f,
+                "Unknown unit. Ensure it is a duration (ns..d), byte size (B, kB, KiB..) or SI prefix (k..P) (was: {})",
+                bad
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParseUnitError {}
+
+const DURATION: &[Unit] = &[
+    Unit::new("ns", 1e-9, UnitFamily::Duration),
+    Unit::new("us", 1e-6, UnitFamily::Duration),
+    Unit::new("ms", 1e-3, UnitFamily::Duration),
+    Unit::new("s", 1.0, UnitFamily::Duration),
+    Unit::new("m", 60.0, UnitFamily::Duration),
+    Unit::new("h", 3600.0, UnitFamily::Duration),
+    Unit::new("d", 86400.0, UnitFamily::Duration),
+];
+
+const DECIMAL_BYTES: &[Unit] = &[
+    Unit::new("B", 1.0, UnitFamily::DecimalBytes),
+    Unit::new("kB", 1e3, UnitFamily::DecimalBytes),
+    Unit::new("MB", 1e6, UnitFamily::DecimalBytes),
+    Unit::new("GB", 1e9, UnitFamily::DecimalBytes),
+    Unit::new("TB", 1e12, UnitFamily::DecimalBytes),
+    Unit::new("PB", 1e15, UnitFamily::DecimalBytes),
+];
+
+const BINARY_BYTES: &[Unit] = &[
+    Unit::new("B", 1.0, UnitFamily::BinaryBytes),
+    Unit::new("KiB", 1024.0, UnitFamily::BinaryBytes),
+    Unit::new("MiB", 1048576.0, UnitFamily::BinaryBytes),
+    Unit::new("GiB", 1073741824.0, UnitFamily::BinaryBytes),
+    Unit::new("TiB", 1099511627776.0, UnitFamily::BinaryBytes),
+    Unit::new("PiB", 1125899906842624.0, UnitFamily::BinaryBytes),
+];
+
+const SI: &[Unit] = &[
+    Unit::new("", 1.0, UnitFamily::Si),
+    Unit::new("k", 1e3, UnitFamily::Si),
+    Unit::new("M", 1e6, UnitFamily::Si),
+    Unit::new("G", 1e9, UnitFamily::Si),
+    Unit::new("T", 1e12, UnitFamily::Si),
+    Unit::new("P", 1e15, UnitFamily::Si),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_quantity() {
+        let data = vec![
+            ("250ms", 0.25, Some("ms")),
+            ("1.5s", 1.5, Some("s")),
+            ("2min", 120.0, Some("m")),
+            ("4KiB", 4096.0, Some("KiB")),
+            ("1GB", 1e9, Some("GB")),
+            ("1.5k", 1500.0, Some("k")),
+            ("1e6", 1e6, None),
+            ("-19", -19.0, None),
+            ("-2.5e-3", -0.0025, None),
+            ("10 B", 10.0, Some("B")),
+        ];
+
+        for (input, value, suffix) in data {
+            let quantity: Quantity = input.parse().unwrap();
+
+            assert!((quantity.value() - value).abs() < 1e-12, "{}", input);
+            assert_eq!(suffix, quantity.unit().map(|u| u.suffix()), "{}", input);
+        }
+    }
+
+    #[test]
+    fn parse_quantity_err() {
+        for input in &["", "ms", "10xyz", "1..5s", "1.5.s"] {
+            assert!(input.parse::<Quantity>().is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn display_best_unit() {
+        let data = vec![
+            (UnitFamily::Duration, 0.1, "100ms"),
+            (UnitFamily::Duration, 90.0, "1.5m"),
+            (UnitFamily::Duration, 0.0, "0s"),
+            (UnitFamily::BinaryBytes, 1536.0, "1.5KiB"),
+            (UnitFamily::DecimalBytes, 1536.0, "1.536kB"),
+            (UnitFamily::Si, 2e6, "2M"),
+            (UnitFamily::Si, 12.0, "12"),
+        ];
+
+        for (family, value, expected) in data {
+            assert_eq!(expected, family.best_for(value).display(value));
+        }
+    }
+}